@@ -0,0 +1 @@
+curl -X POST -H 'Content-Type: application/json' -H 'Cookie: token=22a78952-5c7a-4de5-a2b0-fa7dc312ff1e' localhost:3030/api/invites -d '{"expires_in_hours":72}'
\ No newline at end of file
@@ -0,0 +1 @@
+curl -X POST -H 'Content-Type: application/json' -H 'Cookie: token=22a78952-5c7a-4de5-a2b0-fa7dc312ff1e' localhost:3030/api/tokens -d '{"name":"sync-script","scope":"read_only"}'
\ No newline at end of file
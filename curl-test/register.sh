@@ -0,0 +1 @@
+curl -XPOST -H 'Content-Type: application/json' localhost:3030/api/register -d '{"username":"newbie","password":"secret","invite_code":"df7ee4ed2adf46a695a39cd7605ca810"}'
\ No newline at end of file
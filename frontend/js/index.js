@@ -53,7 +53,19 @@ function saveCurrentHouse() {
             window.location.href = "login.html";
           }, 2000);
         } else if (response.status === 409) {
-          vant.Toast.fail("已经有其他人修改了数据，请刷新页面");
+          // 409 也可能是房屋已归档或自动合并失败，此时直接显示服务端的提示
+          return response.json().then((resp) => {
+            if (resp.conflicts) {
+              showConflicts(resp.conflicts);
+            } else {
+              vant.Toast.fail(
+                resp.error === "Version Mismatch"
+                  ? "已经有其他人修改了数据，请刷新页面"
+                  : resp.error
+              );
+            }
+            throw new Error(`HTTP error! status: ${response.status}`);
+          });
         }
         throw new Error(`HTTP error! status: ${response.status}`);
       }
@@ -61,24 +73,87 @@ function saveCurrentHouse() {
     })
     .then((data) => {
       houseData.currentHouse.version = data.version;
+      // 与其他人的修改自动合并后，重新获取合并结果
+      if (data.merged) fetchHouseDetail();
     })
     .catch((error) => {
       console.error("Error saving current house:", error);
     });
 }
 
-function addSubItem(name) {
-  if (!currentItem.value.items) currentItem.value.items = [];
-  const newItem = {
-    id: String(Math.random()).substring(2),
+const conflictFields = {
+  name: "名称",
+  content: "内容",
+  images: "图片",
+  parent: "位置",
+  area: "删除",
+};
+
+// 自动合并失败时，列出与其他人修改冲突的区域
+function showConflicts(conflicts) {
+  const lines = conflicts.map(
+    (c) => `「${c.area_name || c.area_id}」的${conflictFields[c.field] || c.field}`
+  );
+  vant.Dialog.alert({
+    title: "保存失败",
+    message: `以下内容已经被其他人修改：\n${lines.join("\n")}\n请刷新页面后重新修改`,
+  });
+}
+
+// 调用按区域修改的接口，只提交变化的部分，失败时返回 null
+async function requestArea(method, path, body) {
+  const response = await fetch(
+    `/api/houses/${houseData.currentHouseId}/areas${path}`,
+    {
+      method,
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: body && JSON.stringify(body),
+    }
+  );
+  const resp = await response.json();
+  if (!response.ok) {
+    if (response.status === 401) {
+      vant.Toast.fail("请先登录后，再进行操作");
+      setTimeout(() => {
+        window.location.href = "login.html";
+      }, 2000);
+    } else if (resp.error === "Version Mismatch") {
+      vant.Toast.fail("该区域已经被其他人修改，请刷新页面");
+    } else {
+      vant.Toast.fail(resp.error);
+    }
+    return null;
+  }
+  houseData.currentHouse.version = resp.version;
+  return resp;
+}
+
+// 保存当前区域的部分字段，位于房屋顶层时整体保存
+async function saveCurrentItem(fields) {
+  const item = currentItem.value;
+  if (navItems.value.length <= 1) {
+    saveCurrentHouse();
+    return;
+  }
+  const resp = await requestArea("PATCH", `/${item.id}`, {
+    version: item.version,
+    ...fields,
+  });
+  if (resp) item.version = resp.area.version;
+}
+
+async function addSubItem(name) {
+  const parent = currentItem.value;
+  const resp = await requestArea("POST", "", {
+    parent_id: navItems.value.length > 1 ? parent.id : null,
     name,
-    content: "",
-    items: [],
-    images: [],
-  };
-  currentItem.value.items.push(newItem);
-  setTimeout(saveCurrentHouse);
-  return newItem;
+  });
+  if (!resp) return null;
+  if (!parent.items) parent.items = [];
+  parent.items.push(resp.area);
+  return resp.area;
 }
 
 function selectItem(itemId) {
@@ -86,16 +161,33 @@ function selectItem(itemId) {
   localStorage.setItem("housekeeper_item_current", itemId);
 }
 
+// 列出所有子区域的 id:version，删除时服务端据此确认子区域没有被别人修改
+function collectVersions(items) {
+  return items.flatMap((item) => [
+    `${item.id}:${item.version || ""}`,
+    ...collectVersions(item.items || []),
+  ]);
+}
+
 function deleteCurrentItem() {
   const navSize = navItems.value.length;
   if (navSize > 1) {
     const parentItem = navItems.value[navSize - 2];
+    const current = currentItem.value;
     if (parentItem && parentItem.items && parentItem.items.length) {
-      parentItem.items = parentItem.items.filter(
-        (item) => item.id != houseData.currentItemId
-      );
-      selectItem(parentItem.id);
-      setTimeout(saveCurrentHouse);
+      const descendants = collectVersions(current.items || []).join(",");
+      requestArea(
+        "DELETE",
+        `/${current.id}?version=${encodeURIComponent(
+          current.version || ""
+        )}&descendants=${encodeURIComponent(descendants)}`
+      ).then((resp) => {
+        if (!resp) return;
+        parentItem.items = parentItem.items.filter(
+          (item) => item.id != current.id
+        );
+        selectItem(parentItem.id);
+      });
     }
   } else {
     // 调用后端接口删除当前房屋
@@ -163,10 +255,13 @@ async function handleFileChange(event) {
 
       try {
         // 调用服务端的图片上传接口
-        const response = await fetch("/api/images", {
-          method: "POST",
-          body: formData,
-        });
+        const response = await fetch(
+          `/api/images?house_id=${encodeURIComponent(houseData.currentHouseId)}`,
+          {
+            method: "POST",
+            body: formData,
+          }
+        );
 
         if (!response.ok) {
           throw new Error(`HTTP error! status: ${response.status}`);
@@ -183,7 +278,7 @@ async function handleFileChange(event) {
         console.error("Error uploading image:", error);
       }
     }
-    setTimeout(saveCurrentHouse);
+    saveCurrentItem({ images: currentItem.value.images });
     // 清空文件输入框
     event.target.value = "";
   }
@@ -238,7 +333,7 @@ async function compressImage(file) {
 function deleteImage(index) {
   if (currentItem.value.images) {
     currentItem.value.images.splice(index, 1);
-    setTimeout(saveCurrentHouse);
+    saveCurrentItem({ images: currentItem.value.images });
   }
 }
 
@@ -290,7 +385,7 @@ if (!houseData.currentHouseId) {
       },
       handleRename() {
         currentItem.value.name = this.newName;
-        setTimeout(saveCurrentHouse);
+        saveCurrentItem({ name: this.newName });
       },
       // 处理移动选项
       handleMove() {
@@ -299,12 +394,23 @@ if (!houseData.currentHouseId) {
         this.treeData = [generateTreeData(houseData.currentHouse)];
       },
       // 处理移动对话框确认事件
-      handleMoveConfirm() {
+      async handleMoveConfirm() {
         // console.log("this.selectItemId", this.selectItemId);
         if (this.selectItemId) {
           const navSize = navItems.value.length;
           if (navSize > 1) {
             const current = currentItem.value;
+            const targetItemChain = findItemChain(this.selectItemId);
+            // 目标为房屋本身时移动到顶层
+            const resp = await requestArea("POST", `/${current.id}/move`, {
+              version: current.version,
+              parent_id: targetItemChain.length > 1 ? this.selectItemId : null,
+            });
+            if (!resp) {
+              this.moveModalVisible = false;
+              return;
+            }
+            current.version = resp.area.version;
             // 从原父项中移除当前项
             const parentItem = navItems.value[navSize - 2];
             // console.log("parentItem", parentItem);
@@ -314,7 +420,6 @@ if (!houseData.currentHouseId) {
               );
             }
             // 添加到目标项
-            const targetItemChain = findItemChain(this.selectItemId);
             if (targetItemChain.length > 0) {
               const targetParentItem =
                 targetItemChain[targetItemChain.length - 1];
@@ -323,7 +428,6 @@ if (!houseData.currentHouseId) {
               }
               targetParentItem.items.push(current);
             }
-            setTimeout(saveCurrentHouse);
           }
         }
         this.moveModalVisible = false;
@@ -350,7 +454,7 @@ if (!houseData.currentHouseId) {
         }
         switch (value) {
           case "save":
-            setTimeout(saveCurrentHouse);
+            saveCurrentItem({ content: currentItem.value.content });
             break;
           case "addArea":
             this.addModalVisible = true;
@@ -9,6 +9,24 @@ const app = Vue.createApp({
     };
   },
   methods: {
+    async verifySecondFactor(challenge) {
+      const code = window.prompt("请输入验证器中的 6 位验证码或恢复码");
+      if (!code) {
+        throw new Error("已取消两步验证");
+      }
+      const response = await fetch("/api/login/totp", {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ challenge, code }),
+      });
+      if (!response.ok) {
+        const error = await response.json();
+        throw new Error(error.error || "验证码错误");
+      }
+      return response.json();
+    },
     async handleLogin() {
       this.loading = true;
       try {
@@ -25,7 +43,10 @@ const app = Vue.createApp({
           throw new Error(error.error || "登录失败");
         }
 
-        const data = await response.json();
+        let data = await response.json();
+        if (data.second_factor_required) {
+          data = await this.verifySecondFactor(data.challenge);
+        }
         vant.Toast.success(`欢迎回来，${data.username}`);
         setTimeout(() => {
           window.location.href = "index.html";
@@ -7,13 +7,17 @@ const houseData = reactive({
   memberModalVisible: false,
   currentHouse: {},
   newMemberUsername: "",
+  showArchived: false,
 });
 
 async function fetchHouses() {
   try {
-    const response = await fetch("/api/houses", {
-      headers: {},
-    });
+    const response = await fetch(
+      `/api/houses?include_archived=${houseData.showArchived}`,
+      {
+        headers: {},
+      }
+    );
     if (!response.ok) {
       if (response.status === 401) {
         Toast.fail("鉴权失败，请重新登录");
@@ -28,6 +32,11 @@ async function fetchHouses() {
     houseData.currentUserId = resp.user_id;
     houseData.houses = resp.houses;
     if (houseData.houses.length === 0) {
+      // 只有已归档的房屋时直接显示出来
+      if (!houseData.showArchived) {
+        houseData.showArchived = true;
+        return fetchHouses();
+      }
       Toast.fail("您还没有房屋，请先创建一个");
       setTimeout(() => {
         window.location.href = "house-create.html";
@@ -42,20 +51,80 @@ async function fetchHouses() {
   }
 }
 
-fetchHouses();
+// 通过分享链接 my-houses.html?join={code} 加入房屋
+async function joinByLink() {
+  const code = new URLSearchParams(window.location.search).get("join");
+  if (!code) return;
+  const response = await fetch(`/api/join/${encodeURIComponent(code)}`, {
+    method: "POST",
+  });
+  const resp = await response.json();
+  if (response.ok) {
+    Toast.success("已加入房屋");
+  } else {
+    Toast.fail(resp.error);
+  }
+  history.replaceState(null, "", "my-houses.html");
+}
+
+// 逐个询问是否接受待处理的房屋邀请
+async function handlePendingInvitations() {
+  const response = await fetch("/api/invitations");
+  if (!response.ok) return;
+  const invitations = await response.json();
+  for (const invitation of invitations) {
+    const action = await Dialog.confirm({
+      title: "房屋邀请",
+      message: `${invitation.inviter_username || ""} 邀请你加入「${invitation.house_name}」`,
+      confirmButtonText: "接受",
+      cancelButtonText: "拒绝",
+    })
+      .then(() => "accept")
+      .catch(() => "decline");
+    await fetch(`/api/invitations/${invitation.id}/${action}`, {
+      method: "POST",
+    });
+  }
+}
+
+joinByLink()
+  .then(handlePendingInvitations)
+  .finally(fetchHouses);
 
 async function saveHouseMembers() {
   try {
     const houseId = houseData.currentHouse.id;
-    const usernames = houseData.currentHouse.members.map(
-      (member) => member.username
+    // 新成员需要对方接受邀请后才会加入
+    const newMembers = houseData.currentHouse.members.filter(
+      (member) => !member.user_id
     );
+    for (const member of newMembers) {
+      const response = await fetch(`/api/houses/${houseId}/invitations`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ username: member.username }),
+      });
+      if (!response.ok) {
+        const resp = await response.json();
+        throw new Error(resp.error || `HTTP error! status: ${response.status}`);
+      }
+    }
+
+    const existingMembers = houseData.currentHouse.members.filter(
+      (member) => member.user_id
+    );
+    const usernames = existingMembers.map((member) => member.username);
+    // 保留已有成员的角色
+    const roles = {};
+    existingMembers.forEach((member) => (roles[member.username] = member.role));
     const response = await fetch(`/api/houses/${houseId}/members`, {
       method: "PUT",
       headers: {
         "Content-Type": "application/json",
       },
-      body: JSON.stringify({ usernames }),
+      body: JSON.stringify({ usernames, roles }),
     });
 
     if (!response.ok) {
@@ -70,7 +139,7 @@ async function saveHouseMembers() {
     }
 
     const resp = await response.json();
-    Toast.success(resp.message);
+    Toast.success(newMembers.length ? "已发送邀请，对方接受后加入" : resp.message);
     houseData.memberModalVisible = false;
   } catch (error) {
     console.error("Error saving house members:", error);
@@ -107,6 +176,50 @@ const app = createApp({
         this.houseData.newMemberUsername = "";
       }
     },
+    // 归档后房屋只读，不能再修改数据、上传图片或调整成员
+    setArchived(house, archived) {
+      Dialog.confirm({
+        message: archived
+          ? `归档后「${house.name}」将变为只读，确定归档吗？`
+          : `确定取消归档「${house.name}」吗？`,
+      })
+        .then(async () => {
+          const response = await fetch(`/api/houses/${house.id}/archived`, {
+            method: "PUT",
+            headers: {
+              "Content-Type": "application/json",
+            },
+            body: JSON.stringify({ archived }),
+          });
+          const resp = await response.json();
+          if (!response.ok) {
+            Toast.fail(resp.error);
+            return;
+          }
+          Toast.success(archived ? "已归档" : "已取消归档");
+          fetchHouses();
+        })
+        .catch(() => {});
+    },
+    toggleShowArchived() {
+      fetchHouses();
+    },
+    leaveHouse(house) {
+      Dialog.confirm({ message: `确定退出「${house.name}」吗？` })
+        .then(async () => {
+          const response = await fetch(`/api/houses/${house.id}/leave`, {
+            method: "POST",
+          });
+          const resp = await response.json();
+          if (!response.ok) {
+            Toast.fail(resp.error);
+            return;
+          }
+          Toast.success(resp.message);
+          fetchHouses();
+        })
+        .catch(() => {});
+    },
     deleteMember(index) {
       this.houseData.currentHouse.members.splice(index, 1);
     },
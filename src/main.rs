@@ -2,7 +2,8 @@ mod models;
 mod routes;
 mod storage;
 
-use crate::models::error::AppError;
+use crate::models::{error::AppError, session::session_ttl, trash::trash_retention};
+use routes::proxy_auth::ProxyAuthConfig;
 use std::convert::Infallible;
 use storage::file_storage::FileStorage;
 use warp::{http::StatusCode, Filter, Reply};
@@ -28,6 +29,21 @@ async fn main() {
         "HOUSE_KEEPER_STATIC_PATH: {}",
         std::env::var("HOUSE_KEEPER_STATIC_PATH").unwrap()
     );
+    log::info!(
+        "HOUSE_KEEPER_SESSION_TTL_DAYS: {}",
+        session_ttl().num_days()
+    );
+    log::info!(
+        "HOUSE_KEEPER_TRASH_RETENTION_DAYS: {}",
+        trash_retention().num_days()
+    );
+    if let Some(config) = ProxyAuthConfig::get() {
+        log::info!(
+            "HOUSE_KEEPER_PROXY_AUTH_HEADER: {} (trusted proxies: {:?})",
+            config.header,
+            config.trusted_proxies
+        );
+    }
     log::info!(
         "HOUSE_KEEPER_PORT: {}",
         std::env::var("HOUSE_KEEPER_PORT").unwrap_or_else(|_| "3030".to_string())
@@ -40,10 +56,21 @@ async fn main() {
     std::fs::create_dir_all(format!("{}/images", data_dir))
         .expect("Failed to create images directory");
 
-    for file in &["user.json", "house.json"] {
+    for file in &[
+        "user.json",
+        "house.json",
+        "house_invitation.json",
+        "image.json",
+        "invite.json",
+        "session.json",
+        "template.json",
+        "trash.json",
+        "api_token.json",
+        "password_reset.json",
+    ] {
         let path = format!("{}/{}", data_dir, file);
         if !std::path::Path::new(&path).exists() {
-            std::fs::write(&path, "[]").expect(&format!("Failed to initialize {}", file));
+            std::fs::write(&path, "[]").unwrap_or_else(|_| panic!("Failed to initialize {}", file));
         }
     }
 
@@ -54,6 +81,27 @@ async fn main() {
         std::env::var("HOUSE_KEEPER_STATIC_PATH").expect("HOUSE_KEEPER_STATIC_PATH must be set");
     let file_storage: FileStorage = FileStorage::new(&storage_path);
 
+    // 修复房屋列表与房屋详细数据之间不一致的名称
+    match storage::consistency::sync_house_names(&file_storage) {
+        Ok(0) => {}
+        Ok(repaired) => log::info!("Repaired {} inconsistent house(s)", repaired),
+        Err(e) => log::error!("House consistency check failed: {}", e),
+    }
+
+    // 每小时彻底清除一次回收站中超过保留期的房屋
+    let purge_storage = file_storage.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            match storage::trash::purge_expired(&purge_storage) {
+                Ok(0) => {}
+                Ok(purged) => log::info!("Purged {} house(s) from trash", purged),
+                Err(e) => log::error!("Failed to purge trash: {}", e),
+            }
+        }
+    });
+
     // 初始化路由
     let routes = routes::combine_routes(file_storage, static_path).recover(handle_rejection);
 
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use warp::http::Method;
+
+// 最近使用时间的刷新间隔（秒），避免每个请求都写 api_token.json
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    ReadOnly,
+    ReadWrite,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiToken {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub token_hash: String,
+    pub scope: TokenScope,
+    // 为空表示不限制房屋
+    pub house_ids: Option<Vec<String>>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateApiTokenForm {
+    pub name: String,
+    pub scope: TokenScope,
+    pub house_ids: Option<Vec<String>>,
+}
+
+impl ApiToken {
+    // 返回新建的令牌以及只展示一次的明文 token
+    pub fn new(user_id: &str, form: &CreateApiTokenForm) -> (Self, String) {
+        let secret = format!("hk_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let token = Self {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            name: form.name.clone(),
+            token_hash: Self::hash(&secret),
+            scope: form.scope,
+            house_ids: form.house_ids.clone(),
+            created_at: Utc::now(),
+            last_used_at: None,
+        };
+        (token, secret)
+    }
+
+    // token 本身是高熵随机串，使用 SHA-256 存储即可按哈希直接查找
+    pub fn hash(secret: &str) -> String {
+        format!("{:x}", Sha256::digest(secret.as_bytes()))
+    }
+
+    pub fn can_access_house(&self, house_id: &str) -> bool {
+        self.house_ids
+            .as_ref()
+            .is_none_or(|ids| ids.iter().any(|id| id == house_id))
+    }
+
+    // 校验令牌的权限范围是否允许访问该接口
+    pub fn permits(&self, method: &Method, path: &str) -> bool {
+        let read_only = *method == Method::GET || *method == Method::HEAD;
+        if self.scope == TokenScope::ReadOnly && !read_only {
+            return false;
+        }
+
+        let mut segments = path.trim_start_matches('/').split('/');
+        if segments.next() != Some("api") {
+            return false;
+        }
+        let unrestricted = self.house_ids.is_none();
+        match (segments.next(), segments.next(), segments.next()) {
+            // 复制房屋、另存为模板会在授权的房屋之外创建数据
+            (Some("houses"), Some(house_id), Some("duplicate" | "template")) => {
+                unrestricted && self.can_access_house(house_id)
+            }
+            (Some("houses"), Some(house_id), _) if !house_id.is_empty() => {
+                self.can_access_house(house_id)
+            }
+            // 限定房屋的令牌不能创建新房屋
+            (Some("houses"), _, _) => read_only || unrestricted,
+            // 图片接口自行校验令牌授权的房屋
+            (Some("images"), _, _) => true,
+            (Some("users" | "templates"), _, _) => unrestricted,
+            // 其余接口（令牌、会话、账号、管理、邀请、回收站等）只能通过浏览器登录后使用
+            _ => false,
+        }
+    }
+
+    // 刷新最近使用时间，返回是否需要持久化
+    pub fn touch(&mut self) -> bool {
+        let now = Utc::now();
+        if self
+            .last_used_at
+            .is_some_and(|t| now - t < Duration::seconds(TOUCH_INTERVAL_SECS))
+        {
+            return false;
+        }
+        self.last_used_at = Some(now);
+        true
+    }
+}
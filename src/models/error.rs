@@ -1,15 +1,11 @@
-use std::{convert::Infallible, fmt};
-use warp::{
-    http::StatusCode,
-    reject::{Reject, Rejection},
-    Filter, Reply,
-};
+use crate::models::merge::MergeConflict;
+use std::fmt;
+use warp::{http::StatusCode, reject::Reject};
 
 #[derive(Debug)]
 pub enum AppError {
     AuthenticationRequired,
     PermissionDenied,
-    InvalidVersion,
     FileSystemError(String),
     ParseError(String),
     NotFound,
@@ -20,6 +16,24 @@ pub enum AppError {
     VersionMismatch,
     ParameterError,
     BackupError,
+    UsernameTaken,
+    InvalidInviteCode,
+    TooManyAttempts,
+    InvalidResetCode,
+    AccountDisabled,
+    CrossSiteRequest,
+    InvalidSecondFactor,
+    HousesStillOwned(Vec<String>),
+    UnknownUsernames(Vec<String>),
+    AlreadyMember,
+    InvitationRequired(Vec<String>),
+    OwnerCannotLeave,
+    HouseArchived,
+    AreaNotFound,
+    UnsupportedMediaType(String),
+    InvalidPatch(String),
+    PatchTestFailed(String),
+    MergeConflict(Vec<MergeConflict>),
 }
 
 impl fmt::Display for AppError {
@@ -27,52 +41,66 @@ impl fmt::Display for AppError {
         match self {
             AppError::AuthenticationRequired => write!(f, "Authentication required"),
             AppError::PermissionDenied => write!(f, "Permission denied"),
-            AppError::InvalidVersion => write!(f, "Invalid version"),
             AppError::FileSystemError(msg) => write!(f, "File system error: {}", msg),
             AppError::ParseError(msg) => write!(f, "Parse error: {}", msg),
             AppError::NotFound => write!(f, "Resource not found"),
             AppError::InternalServerError => write!(f, "Internal server error"),
             AppError::UserNotFound => write!(f, "User not found"),
             AppError::HouseNotFound => write!(f, "House not found"),
-            AppError::PasswordError => write!(f, "Invalid password"),
+            AppError::PasswordError => write!(f, "Invalid username or password"),
             AppError::VersionMismatch => write!(f, "Version Mismatch"),
             AppError::ParameterError => write!(f, "Parameter Error"),
             AppError::BackupError => write!(f, "Backup Error"),
+            AppError::UsernameTaken => write!(f, "Username already exists"),
+            AppError::InvalidInviteCode => write!(f, "Invalid or expired invite code"),
+            AppError::TooManyAttempts => {
+                write!(f, "Too many failed login attempts, please try again later")
+            }
+            AppError::InvalidResetCode => write!(f, "Invalid or expired reset code"),
+            AppError::AccountDisabled => write!(f, "Account disabled"),
+            AppError::CrossSiteRequest => write!(f, "Cross-site request rejected"),
+            AppError::InvalidSecondFactor => write!(f, "Invalid verification code"),
+            AppError::HousesStillOwned(ids) => write!(
+                f,
+                "Transfer or delete these houses first: {}",
+                ids.join(", ")
+            ),
+            AppError::UnknownUsernames(names) => {
+                write!(f, "Unknown usernames: {}", names.join(", "))
+            }
+            AppError::AlreadyMember => write!(f, "User is already a member of this house"),
+            AppError::OwnerCannotLeave => write!(
+                f,
+                "The creator cannot leave the house, transfer or delete it instead"
+            ),
+            AppError::HouseArchived => write!(f, "House is archived and read-only"),
+            AppError::AreaNotFound => write!(f, "Area not found"),
+            AppError::UnsupportedMediaType(content_type) => {
+                write!(f, "Unsupported content type: {}", content_type)
+            }
+            AppError::InvalidPatch(msg) => write!(f, "Invalid patch: {}", msg),
+            AppError::PatchTestFailed(msg) => write!(f, "Patch test failed: {}", msg),
+            AppError::MergeConflict(conflicts) => write!(
+                f,
+                "Conflicting changes in {} area(s), please resolve them and save again",
+                conflicts.len()
+            ),
+            AppError::InvitationRequired(names) => write!(
+                f,
+                "These users must accept an invitation first: {}",
+                names.join(", ")
+            ),
         }
     }
 }
 
 impl Reject for AppError {}
 
-pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
-    let (code, message) = if let Some(e) = err.find::<AppError>() {
-        (e.status_code(), e.to_string())
-    } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
-        (
-            StatusCode::METHOD_NOT_ALLOWED,
-            "Method not allowed".to_string(),
-        )
-    } else {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Internal server error".to_string(),
-        )
-    };
-
-    Ok(warp::reply::with_status(
-        warp::reply::json(&serde_json::json!({
-            "message": message
-        })),
-        code,
-    ))
-}
-
 impl AppError {
     pub fn status_code(&self) -> StatusCode {
         match self {
             AppError::AuthenticationRequired => StatusCode::UNAUTHORIZED,
             AppError::PermissionDenied => StatusCode::FORBIDDEN,
-            AppError::InvalidVersion => StatusCode::CONFLICT,
             AppError::FileSystemError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::ParseError(_) => StatusCode::BAD_REQUEST,
             AppError::NotFound => StatusCode::NOT_FOUND,
@@ -83,6 +111,24 @@ impl AppError {
             AppError::VersionMismatch => StatusCode::CONFLICT,
             AppError::ParameterError => StatusCode::BAD_REQUEST,
             AppError::BackupError => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::UsernameTaken => StatusCode::CONFLICT,
+            AppError::InvalidInviteCode => StatusCode::BAD_REQUEST,
+            AppError::TooManyAttempts => StatusCode::TOO_MANY_REQUESTS,
+            AppError::InvalidResetCode => StatusCode::BAD_REQUEST,
+            AppError::AccountDisabled => StatusCode::FORBIDDEN,
+            AppError::CrossSiteRequest => StatusCode::FORBIDDEN,
+            AppError::InvalidSecondFactor => StatusCode::UNAUTHORIZED,
+            AppError::UnknownUsernames(_) => StatusCode::BAD_REQUEST,
+            AppError::AlreadyMember => StatusCode::CONFLICT,
+            AppError::InvitationRequired(_) => StatusCode::BAD_REQUEST,
+            AppError::OwnerCannotLeave => StatusCode::CONFLICT,
+            AppError::HouseArchived => StatusCode::CONFLICT,
+            AppError::AreaNotFound => StatusCode::NOT_FOUND,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::InvalidPatch(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::PatchTestFailed(_) => StatusCode::CONFLICT,
+            AppError::MergeConflict(_) => StatusCode::CONFLICT,
+            AppError::HousesStillOwned(_) => StatusCode::CONFLICT,
         }
     }
 }
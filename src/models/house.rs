@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -7,22 +9,123 @@ pub struct House {
     pub name: String,
     pub creator: String,
     pub members: Vec<HouseMember>,
+    // 创建人发起、尚未被接受的转让
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_transfer: Option<OwnershipTransfer>,
+    // 已归档的房屋只读，不能修改数据、上传图片或调整成员
+    #[serde(default)]
+    pub archived: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OwnershipTransfer {
+    // 被提名的新创建人，必须是房屋成员
+    pub to: String,
+    pub requested_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransferOwnershipForm {
+    pub user_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CreateHouseForm {
     pub name: String,
+    // 从模板创建时指定模板 ID
+    #[serde(default)]
+    pub template_id: Option<String>,
+}
+
+// 复制房屋或保存模板时复制哪些内容
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyMode {
+    // 只复制区域结构（名称和层级）
+    #[default]
+    Structure,
+    // 结构和文字内容
+    Content,
+    // 结构、文字内容和图片引用
+    Full,
+}
+
+impl CopyMode {
+    // 复制图片引用会把图片的查看权限带到新房屋，需要 editor 及以上角色
+    pub fn min_role(self) -> HouseRole {
+        match self {
+            CopyMode::Structure | CopyMode::Content => HouseRole::Viewer,
+            CopyMode::Full => HouseRole::Editor,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateHouseForm {
+    // 新房屋名称，不填时在原名称后加上“副本”
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub mode: CopyMode,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RenameHouseForm {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchiveHouseForm {
+    pub archived: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HouseListQuery {
+    // 默认不返回已归档的房屋
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+// 房屋成员角色，按权限从低到高排列；owner 即房屋创建人，不能分配给成员
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HouseRole {
+    // 只能查看
+    Viewer,
+    // 可以修改详细数据、上传图片，旧数据中的成员默认为该角色
+    #[default]
+    Editor,
+    // 还可以管理成员、重命名房屋
+    Manager,
+    Owner,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct HouseMember {
     pub user_id: String,
     pub username: String,
+    #[serde(default)]
+    pub role: HouseRole,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SetHouseMembersForm {
     pub usernames: Vec<String>,
+    // 用户名 -> 角色，未指定时保留原有角色，新成员默认为 editor
+    #[serde(default)]
+    pub roles: HashMap<String, HouseRole>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AddHouseMemberForm {
+    pub username: String,
+    #[serde(default)]
+    pub role: HouseRole,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetMemberRoleForm {
+    pub role: HouseRole,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,12 +138,78 @@ pub struct HouseDetail {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct HouseArea {
     pub id: String,
+    // 区域自身的版本号，名称、内容、图片或所在位置变化时更新，子区域变化不影响
+    // 旧数据中没有该字段，视为空字符串
+    #[serde(default)]
+    pub version: String,
     pub name: String,
     pub content: String,
     pub images: Vec<String>,
     pub items: Vec<HouseArea>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateAreaForm {
+    // 父区域 ID，不填时添加到房屋的顶层
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    // 在同级区域中的位置，不填时追加到末尾
+    #[serde(default)]
+    pub position: Option<usize>,
+    pub name: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub images: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateAreaForm {
+    // 客户端看到的区域版本号，与当前版本不一致时返回冲突
+    pub version: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub images: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MoveAreaForm {
+    pub version: String,
+    // 新的父区域 ID，不填时移动到房屋的顶层
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub position: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeleteAreaQuery {
+    #[serde(default)]
+    pub version: Option<String>,
+    // 客户端看到的所有子区域的版本号，格式为 id:version，多个之间用逗号分隔
+    #[serde(default)]
+    pub descendants: Option<String>,
+}
+
+impl DeleteAreaQuery {
+    // 解析 descendants，格式错误时返回 None
+    pub fn descendant_versions(&self) -> Option<HashMap<String, String>> {
+        self.descendants
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                pair.split_once(':')
+                    .map(|(id, version)| (id.to_string(), version.to_string()))
+            })
+            .collect()
+    }
+}
+
 impl House {
     pub fn new(name: &str, creator: &str) -> Self {
         Self {
@@ -48,8 +217,35 @@ impl House {
             name: name.to_string(),
             creator: creator.to_string(),
             members: Vec::new(),
+            pending_transfer: None,
+            archived: false,
         }
     }
+
+    // 把创建人转给某个成员；提供原创建人的用户名时，原创建人以 editor 角色留在成员中
+    pub fn transfer_to(&mut self, new_owner: &str, former_username: Option<String>) {
+        let former = std::mem::replace(&mut self.creator, new_owner.to_string());
+        self.members.retain(|m| m.user_id != new_owner);
+        if let Some(username) = former_username {
+            self.members.push(HouseMember {
+                user_id: former,
+                username,
+                role: HouseRole::Editor,
+            });
+        }
+        self.pending_transfer = None;
+    }
+
+    // 查询用户在房屋中的角色，非创建人也非成员时返回 None
+    pub fn role_of(&self, user_id: &str) -> Option<HouseRole> {
+        if self.creator == user_id {
+            return Some(HouseRole::Owner);
+        }
+        self.members
+            .iter()
+            .find(|m| m.user_id == user_id)
+            .map(|m| m.role)
+    }
 }
 
 impl HouseDetail {
@@ -60,4 +256,202 @@ impl HouseDetail {
             items: Vec::new(),
         }
     }
+
+    // 生成新的版本号并返回，任何修改都要更新
+    pub fn touch(&mut self) -> String {
+        self.version = Uuid::new_v4().to_string();
+        self.version.clone()
+    }
+
+    // 按 ID 查找区域
+    pub fn find_area_mut(&mut self, area_id: &str) -> Option<&mut HouseArea> {
+        find_area_mut(&mut self.items, area_id)
+    }
+
+    // 父区域的子区域列表，parent_id 为空时返回顶层区域
+    pub fn children_mut(&mut self, parent_id: Option<&str>) -> Option<&mut Vec<HouseArea>> {
+        match parent_id {
+            Some(id) => self.find_area_mut(id).map(|a| &mut a.items),
+            None => Some(&mut self.items),
+        }
+    }
+
+    // 移除并返回区域及其子区域
+    pub fn remove_area(&mut self, area_id: &str) -> Option<HouseArea> {
+        remove_area(&mut self.items, area_id)
+    }
+
+    // 整体保存时，与 previous 相比名称、内容、图片或父区域有变化的区域以及新区域生成新版本号，
+    // 其余区域沿用原来的版本号，忽略客户端提交的版本号
+    pub fn stamp_versions(&mut self, previous: &HouseDetail) {
+        let mut old = HashMap::new();
+        index_areas(&previous.items, None, &mut old);
+        stamp_versions(&mut self.items, None, &old);
+    }
+
+    // 校验区域 ID 不为空且不重复，返回第一个问题
+    pub fn validate(&self) -> Result<(), String> {
+        check_area_ids(&self.items, &mut HashSet::new())
+    }
+
+    // 是否有区域引用了指定文件名的图片
+    pub fn references_image(&self, file_name: &str) -> bool {
+        self.items.iter().any(|a| a.references_image(file_name))
+    }
+
+    // 所有区域引用的图片文件名
+    pub fn image_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        self.items
+            .iter()
+            .for_each(|a| a.collect_image_names(&mut names));
+        names
+    }
+}
+
+impl HouseArea {
+    pub fn new(name: &str, content: String, images: Vec<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().simple().to_string(),
+            version: Uuid::new_v4().simple().to_string(),
+            name: name.to_string(),
+            content,
+            images,
+            items: Vec::new(),
+        }
+    }
+
+    // 区域有修改时生成新的版本号
+    pub fn touch(&mut self) {
+        self.version = Uuid::new_v4().simple().to_string();
+    }
+
+    // 区域本身或其子区域中是否有指定 ID 的区域
+    pub fn contains(&self, area_id: &str) -> bool {
+        self.id == area_id || self.items.iter().any(|a| a.contains(area_id))
+    }
+
+    // 所有子区域（不含自身）的 id 和版本号
+    pub fn descendant_versions(&self) -> HashMap<String, String> {
+        let mut versions = HashMap::new();
+        self.collect_versions(&mut versions);
+        versions
+    }
+
+    fn collect_versions(&self, versions: &mut HashMap<String, String>) {
+        for area in &self.items {
+            versions.insert(area.id.clone(), area.version.clone());
+            area.collect_versions(versions);
+        }
+    }
+
+    pub fn references_image(&self, file_name: &str) -> bool {
+        // images 中保存的是 /api/images/{file_name} 形式的地址
+        self.images
+            .iter()
+            .any(|url| url.rsplit('/').next() == Some(file_name))
+            || self.items.iter().any(|a| a.references_image(file_name))
+    }
+
+    // 复制区域及其所有子区域，重新生成每个区域的 id
+    pub fn duplicate(&self, mode: CopyMode) -> Self {
+        Self {
+            id: Uuid::new_v4().simple().to_string(),
+            version: Uuid::new_v4().simple().to_string(),
+            name: self.name.clone(),
+            content: match mode {
+                CopyMode::Structure => String::new(),
+                CopyMode::Content | CopyMode::Full => self.content.clone(),
+            },
+            images: match mode {
+                CopyMode::Full => self.images.clone(),
+                CopyMode::Structure | CopyMode::Content => Vec::new(),
+            },
+            items: self.items.iter().map(|a| a.duplicate(mode)).collect(),
+        }
+    }
+
+    // 该区域及其子区域引用的图片文件名
+    pub fn image_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        self.collect_image_names(&mut names);
+        names
+    }
+
+    fn collect_image_names(&self, names: &mut Vec<String>) {
+        names.extend(
+            self.images
+                .iter()
+                .filter_map(|url| url.rsplit('/').next())
+                .map(str::to_string),
+        );
+        self.items.iter().for_each(|a| a.collect_image_names(names));
+    }
+}
+
+fn find_area_mut<'a>(items: &'a mut [HouseArea], area_id: &str) -> Option<&'a mut HouseArea> {
+    for area in items {
+        if area.id == area_id {
+            return Some(area);
+        }
+        if let Some(found) = find_area_mut(&mut area.items, area_id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+fn remove_area(items: &mut Vec<HouseArea>, area_id: &str) -> Option<HouseArea> {
+    if let Some(index) = items.iter().position(|a| a.id == area_id) {
+        return Some(items.remove(index));
+    }
+    items
+        .iter_mut()
+        .find_map(|a| remove_area(&mut a.items, area_id))
+}
+
+// 区域 ID -> (父区域 ID, 区域)
+fn index_areas<'a>(
+    items: &'a [HouseArea],
+    parent_id: Option<&'a str>,
+    index: &mut HashMap<&'a str, (Option<&'a str>, &'a HouseArea)>,
+) {
+    for area in items {
+        index.insert(&area.id, (parent_id, area));
+        index_areas(&area.items, Some(&area.id), index);
+    }
+}
+
+fn stamp_versions(
+    items: &mut [HouseArea],
+    parent_id: Option<&str>,
+    old: &HashMap<&str, (Option<&str>, &HouseArea)>,
+) {
+    for area in items.iter_mut() {
+        match old.get(area.id.as_str()) {
+            Some((old_parent, old_area))
+                if *old_parent == parent_id
+                    && old_area.name == area.name
+                    && old_area.content == area.content
+                    && old_area.images == area.images =>
+            {
+                area.version = old_area.version.clone();
+            }
+            _ => area.touch(),
+        }
+        stamp_versions(&mut area.items, Some(&area.id), old);
+    }
+}
+
+fn check_area_ids<'a>(items: &'a [HouseArea], seen: &mut HashSet<&'a str>) -> Result<(), String> {
+    for area in items {
+        if area.id.is_empty() {
+            return Err(format!("area {:?} has an empty id", area.name));
+        }
+        if !seen.insert(&area.id) {
+            return Err(format!("duplicate area id {:?}", area.id));
+        }
+        check_area_ids(&area.items, seen)?;
+    }
+    Ok(())
 }
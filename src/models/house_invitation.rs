@@ -0,0 +1,60 @@
+use crate::models::house::HouseRole;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// 房屋邀请默认有效期（小时）
+pub const DEFAULT_HOUSE_INVITATION_TTL_HOURS: i64 = 168;
+
+// 房屋邀请：指定用户的邀请由被邀请人接受或拒绝；
+// 分享链接不指定用户，有效期内任何登录用户都可以通过 code 加入
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HouseInvitation {
+    pub id: String,
+    pub house_id: String,
+    pub inviter: String,
+    pub role: HouseRole,
+    // 被邀请人的用户 ID，分享链接为空
+    pub invitee: Option<String>,
+    // 分享链接的加入码，指定用户的邀请为空
+    pub code: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateHouseInvitationForm {
+    // 不填时生成分享链接
+    pub username: Option<String>,
+    #[serde(default)]
+    pub role: HouseRole,
+    pub expires_in_hours: Option<i64>,
+}
+
+impl HouseInvitation {
+    pub fn new(
+        house_id: &str,
+        inviter: &str,
+        role: HouseRole,
+        invitee: Option<String>,
+        ttl: Duration,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            house_id: house_id.to_string(),
+            inviter: inviter.to_string(),
+            role,
+            code: invitee
+                .is_none()
+                .then(|| Uuid::new_v4().simple().to_string()),
+            invitee,
+            created_at: now,
+            expires_at: now + ttl,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
@@ -1,4 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// 上传图片的归属记录，用于下载时校验权限
 #[derive(Debug, Serialize, Deserialize, Clone)]
-struct ImageResponse {
-    filename: String,
+pub struct ImageRecord {
+    pub file_name: String,
+    // 所属房屋，为空时表示个人图片（例如头像），所有登录用户可见
+    pub house_id: Option<String>,
+    pub uploader: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UploadImageQuery {
+    pub house_id: Option<String>,
+}
+
+impl ImageRecord {
+    pub fn new(file_name: &str, house_id: Option<String>, uploader: &str) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            house_id,
+            uploader: uploader.to_string(),
+            created_at: Utc::now(),
+        }
+    }
 }
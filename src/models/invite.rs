@@ -0,0 +1,35 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// 邀请码默认有效期（小时）
+pub const DEFAULT_INVITE_TTL_HOURS: i64 = 72;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InviteCode {
+    pub code: String,
+    pub creator: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateInviteForm {
+    pub expires_in_hours: Option<i64>,
+}
+
+impl InviteCode {
+    pub fn new(creator: &str, ttl: Duration) -> Self {
+        let now = Utc::now();
+        Self {
+            code: Uuid::new_v4().simple().to_string(),
+            creator: creator.to_string(),
+            created_at: now,
+            expires_at: now + ttl,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
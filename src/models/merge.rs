@@ -0,0 +1,586 @@
+use crate::models::house::HouseArea;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+// 三方合并的冲突：双方都修改了同一个区域的同一项，且修改结果不同
+#[derive(Debug, Serialize, Clone)]
+pub struct MergeConflict {
+    pub area_id: String,
+    // 区域名称，便于提示用户
+    pub area_name: String,
+    // name、content、images、parent，或 area 表示一方删除了区域而另一方修改了它
+    pub field: &'static str,
+    // 客户端开始编辑时的值
+    pub base: Value,
+    // 服务端当前保存的值，区域已被删除时为 null
+    pub theirs: Value,
+    // 本次提交的值，区域已被删除时为 null
+    pub yours: Value,
+}
+
+// 区域在某个版本中的位置，以及每个父区域下子区域的顺序
+struct Tree<'a> {
+    areas: HashMap<&'a str, (Option<&'a str>, &'a HouseArea)>,
+    children: HashMap<Option<&'a str>, Vec<&'a str>>,
+    // 遍历顺序，用于得到稳定的合并结果
+    ids: Vec<&'a str>,
+}
+
+impl<'a> Tree<'a> {
+    fn new(items: &'a [HouseArea]) -> Self {
+        let mut tree = Tree {
+            areas: HashMap::new(),
+            children: HashMap::new(),
+            ids: Vec::new(),
+        };
+        tree.add(items, None);
+        tree
+    }
+
+    fn add(&mut self, items: &'a [HouseArea], parent: Option<&'a str>) {
+        for area in items {
+            self.areas.insert(&area.id, (parent, area));
+            self.children.entry(parent).or_default().push(&area.id);
+            self.ids.push(&area.id);
+            self.add(&area.items, Some(&area.id));
+        }
+    }
+
+    fn get(&self, id: &str) -> Option<(Option<&'a str>, &'a HouseArea)> {
+        self.areas.get(id).copied()
+    }
+
+    fn children(&self, parent: Option<&'a str>) -> &[&'a str] {
+        self.children.get(&parent).map_or(&[], |c| c.as_slice())
+    }
+}
+
+// 合并后的区域，子区域在最后统一组装
+struct Merged<'a> {
+    area: HouseArea,
+    parent: Option<&'a str>,
+}
+
+// 以 base 为共同祖先，按区域 ID 合并服务端当前的 theirs 和客户端提交的 yours。
+// 只有一方修改的项直接采用，双方改成相同结果的项也不算冲突
+pub fn merge_areas(
+    base: &[HouseArea],
+    theirs: &[HouseArea],
+    yours: &[HouseArea],
+) -> Result<Vec<HouseArea>, Vec<MergeConflict>> {
+    let base = Tree::new(base);
+    let theirs = Tree::new(theirs);
+    let yours = Tree::new(yours);
+    let mut conflicts = Vec::new();
+    let mut merged: HashMap<&str, Merged> = HashMap::new();
+    let mut order = Vec::new();
+
+    let mut seen = HashSet::new();
+    let ids = theirs
+        .ids
+        .iter()
+        .chain(yours.ids.iter())
+        .chain(base.ids.iter())
+        .filter(|id| seen.insert(**id));
+    for &id in ids {
+        let result = match (base.get(id), theirs.get(id), yours.get(id)) {
+            (_, Some(t), Some(y)) => {
+                // 双方同时新增了相同 ID 的区域时没有共同祖先，以 theirs 为基础，即采用本次提交的内容
+                let b = base.get(id).unwrap_or(t);
+                merge_area(b, t, y, &mut conflicts)
+            }
+            // 只有一方新增
+            (None, Some(side), None) | (None, None, Some(side)) => Some(keep(side)),
+            // 一方删除：另一方没有修改时删除，否则冲突
+            (Some(b), None, Some(y)) => {
+                if changed(b, y) {
+                    conflicts.push(deleted_conflict(b, None, Some(y)));
+                }
+                None
+            }
+            (Some(b), Some(t), None) => {
+                if changed(b, t) {
+                    conflicts.push(deleted_conflict(b, Some(t), None));
+                }
+                None
+            }
+            (Some(_), None, None) | (None, None, None) => None,
+        };
+        if let Some(m) = result {
+            order.push(id);
+            merged.insert(id, m);
+        }
+    }
+
+    // 父区域被一方删除、另一方又在其下新增或移入了区域；
+    // 父区域本身有冲突时已经报告过，不再重复报告为删除
+    let conflicted = conflicts
+        .iter()
+        .map(|c| c.area_id.clone())
+        .collect::<HashSet<_>>();
+    for id in &order {
+        if let Some(parent) = merged[id].parent {
+            let deleted = theirs.get(parent).is_none() || yours.get(parent).is_none();
+            if !merged.contains_key(parent) && !conflicted.contains(parent) && deleted {
+                let b = base.get(parent);
+                let conflict = MergeConflict {
+                    area_id: parent.to_string(),
+                    area_name: b.map(|(_, a)| a.name.clone()).unwrap_or_default(),
+                    field: "area",
+                    base: b.map_or(Value::Null, |(_, a)| summary(a)),
+                    theirs: theirs.get(parent).map_or(Value::Null, |(_, a)| summary(a)),
+                    yours: yours.get(parent).map_or(Value::Null, |(_, a)| summary(a)),
+                };
+                if !conflicts
+                    .iter()
+                    .any(|c| c.area_id == conflict.area_id && c.field == "area")
+                {
+                    conflicts.push(conflict);
+                }
+            }
+        }
+    }
+
+    // 双方把两个区域互相移到对方下面会形成环
+    for id in &order {
+        let mut current = merged[id].parent;
+        let mut steps = 0;
+        while let Some(parent) = current {
+            steps += 1;
+            if parent == *id || steps > order.len() {
+                conflicts.push(MergeConflict {
+                    area_id: id.to_string(),
+                    area_name: merged[id].area.name.clone(),
+                    field: "parent",
+                    base: json!(base.get(id).and_then(|(p, _)| p)),
+                    theirs: json!(theirs.get(id).and_then(|(p, _)| p)),
+                    yours: json!(yours.get(id).and_then(|(p, _)| p)),
+                });
+                break;
+            }
+            current = merged.get(parent).and_then(|m| m.parent);
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+
+    // 按父区域分组，确定子区域的顺序后组装成树
+    let mut groups: HashMap<Option<&str>, HashSet<&str>> = HashMap::new();
+    for id in &order {
+        groups.entry(merged[id].parent).or_default().insert(id);
+    }
+    Ok(build(None, &groups, &mut merged, &base, &theirs, &yours))
+}
+
+fn build<'a>(
+    parent: Option<&'a str>,
+    groups: &HashMap<Option<&'a str>, HashSet<&'a str>>,
+    merged: &mut HashMap<&'a str, Merged<'a>>,
+    base: &Tree<'a>,
+    theirs: &Tree<'a>,
+    yours: &Tree<'a>,
+) -> Vec<HouseArea> {
+    let Some(members) = groups.get(&parent) else {
+        return Vec::new();
+    };
+    let ids = child_order(parent, members, base, theirs, yours);
+    ids.into_iter()
+        .filter_map(|id| {
+            let mut area = merged.remove(id)?.area;
+            area.items = build(Some(id), groups, merged, base, theirs, yours);
+            Some(area)
+        })
+        .collect()
+}
+
+// 子区域的顺序：只有一方调整了原有子区域的顺序时采用该方的顺序，
+// 双方都调整时以本次提交的为准；另一方新增或移入的区域插在它原来的前一个区域之后
+fn child_order<'a>(
+    parent: Option<&'a str>,
+    members: &HashSet<&'a str>,
+    base: &Tree<'a>,
+    theirs: &Tree<'a>,
+    yours: &Tree<'a>,
+) -> Vec<&'a str> {
+    let base_order = base.children(parent);
+    let reordered =
+        |side: &[&str]| common_order(side, base_order) != common_order(base_order, side);
+    let (primary, secondary) = if reordered(yours.children(parent)) {
+        (yours.children(parent), theirs.children(parent))
+    } else {
+        (theirs.children(parent), yours.children(parent))
+    };
+
+    let mut result = primary
+        .iter()
+        .filter(|id| members.contains(*id))
+        .copied()
+        .collect::<Vec<_>>();
+    for (index, id) in secondary.iter().enumerate() {
+        if !members.contains(id) || result.contains(id) {
+            continue;
+        }
+        let position = secondary[..index]
+            .iter()
+            .rev()
+            .find_map(|prev| result.iter().position(|r| r == prev))
+            .map_or(0, |p| p + 1);
+        result.insert(position, id);
+    }
+    // 从其他父区域移入、双方顺序中都没有的区域追加到末尾
+    let mut rest = members
+        .iter()
+        .filter(|id| !result.contains(*id))
+        .copied()
+        .collect::<Vec<_>>();
+    rest.sort();
+    result.extend(rest);
+    result
+}
+
+// order 中同时出现在 other 里的区域，保持 order 中的顺序
+fn common_order<'a>(order: &[&'a str], other: &[&str]) -> Vec<&'a str> {
+    order
+        .iter()
+        .filter(|id| other.contains(id))
+        .copied()
+        .collect()
+}
+
+fn keep<'a>((parent, area): (Option<&'a str>, &'a HouseArea)) -> Merged<'a> {
+    Merged {
+        area: HouseArea {
+            items: Vec::new(),
+            ..area.clone()
+        },
+        parent,
+    }
+}
+
+// 逐项合并同一个区域，有冲突时记录并返回 None
+fn merge_area<'a>(
+    (base_parent, b): (Option<&'a str>, &'a HouseArea),
+    (theirs_parent, t): (Option<&'a str>, &'a HouseArea),
+    (yours_parent, y): (Option<&'a str>, &'a HouseArea),
+    conflicts: &mut Vec<MergeConflict>,
+) -> Option<Merged<'a>> {
+    let before = conflicts.len();
+    let mut field = |name: &'static str, base: Value, theirs: Value, yours: Value| {
+        if yours == base || yours == theirs {
+            Some(theirs)
+        } else if theirs == base {
+            Some(yours)
+        } else {
+            conflicts.push(MergeConflict {
+                area_id: t.id.clone(),
+                area_name: b.name.clone(),
+                field: name,
+                base,
+                theirs,
+                yours,
+            });
+            None
+        }
+    };
+    let name = field("name", json!(b.name), json!(t.name), json!(y.name));
+    let content = field(
+        "content",
+        json!(b.content),
+        json!(t.content),
+        json!(y.content),
+    );
+    let images = field("images", json!(b.images), json!(t.images), json!(y.images));
+    let parent = field(
+        "parent",
+        json!(base_parent),
+        json!(theirs_parent),
+        json!(yours_parent),
+    );
+    if conflicts.len() > before {
+        return None;
+    }
+
+    // 合并结果一定取自三方之一，按值找回对应的父区域 ID
+    let parent = parent.and_then(|p| p.as_str().map(str::to_string));
+    let parent = [theirs_parent, yours_parent]
+        .into_iter()
+        .flatten()
+        .find(|p| Some(*p) == parent.as_deref());
+    Some(Merged {
+        area: HouseArea {
+            id: t.id.clone(),
+            version: t.version.clone(),
+            name: serde_json::from_value(name?).unwrap_or_default(),
+            content: serde_json::from_value(content?).unwrap_or_default(),
+            images: serde_json::from_value(images?).unwrap_or_default(),
+            items: Vec::new(),
+        },
+        parent,
+    })
+}
+
+// 区域本身（不含子区域）是否相对 base 有修改
+fn changed(
+    (base_parent, b): (Option<&str>, &HouseArea),
+    (parent, a): (Option<&str>, &HouseArea),
+) -> bool {
+    base_parent != parent || b.name != a.name || b.content != a.content || b.images != a.images
+}
+
+fn deleted_conflict(
+    (_, b): (Option<&str>, &HouseArea),
+    theirs: Option<(Option<&str>, &HouseArea)>,
+    yours: Option<(Option<&str>, &HouseArea)>,
+) -> MergeConflict {
+    MergeConflict {
+        area_id: b.id.clone(),
+        area_name: b.name.clone(),
+        field: "area",
+        base: summary(b),
+        theirs: theirs.map_or(Value::Null, |(_, a)| summary(a)),
+        yours: yours.map_or(Value::Null, |(_, a)| summary(a)),
+    }
+}
+
+// 冲突中展示的区域内容，不含子区域
+fn summary(area: &HouseArea) -> Value {
+    json!({
+        "name": area.name,
+        "content": area.content,
+        "images": area.images,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(id: &str, items: Vec<HouseArea>) -> HouseArea {
+        HouseArea {
+            id: id.to_string(),
+            version: format!("v-{}", id),
+            name: id.to_uppercase(),
+            content: String::new(),
+            images: Vec::new(),
+            items,
+        }
+    }
+
+    fn with_content(mut area: HouseArea, content: &str) -> HouseArea {
+        area.content = content.to_string();
+        area
+    }
+
+    // 树的结构，例如 a[b,c],d
+    fn shape(items: &[HouseArea]) -> String {
+        items
+            .iter()
+            .map(|a| {
+                if a.items.is_empty() {
+                    a.id.clone()
+                } else {
+                    format!("{}[{}]", a.id, shape(&a.items))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn conflict_fields(conflicts: &[MergeConflict]) -> Vec<(&str, &str)> {
+        conflicts
+            .iter()
+            .map(|c| (c.area_id.as_str(), c.field))
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_sides_keep_base() {
+        let base = vec![area("a", vec![area("b", vec![])]), area("c", vec![])];
+        let merged = merge_areas(&base, &base, &base).unwrap();
+        assert_eq!(shape(&merged), "a[b],c");
+    }
+
+    #[test]
+    fn edits_to_different_fields_are_combined() {
+        let base = vec![area("a", vec![])];
+        let mut theirs = base.clone();
+        theirs[0].name = "Kitchen".to_string();
+        let yours = vec![with_content(area("a", vec![]), "pots")];
+
+        let merged = merge_areas(&base, &theirs, &yours).unwrap();
+        assert_eq!(merged[0].name, "Kitchen");
+        assert_eq!(merged[0].content, "pots");
+    }
+
+    #[test]
+    fn edits_to_different_areas_are_combined() {
+        let base = vec![area("a", vec![]), area("b", vec![])];
+        let theirs = vec![with_content(area("a", vec![]), "x"), area("b", vec![])];
+        let yours = vec![area("a", vec![]), with_content(area("b", vec![]), "y")];
+
+        let merged = merge_areas(&base, &theirs, &yours).unwrap();
+        assert_eq!(merged[0].content, "x");
+        assert_eq!(merged[1].content, "y");
+    }
+
+    #[test]
+    fn identical_edits_do_not_conflict() {
+        let base = vec![area("a", vec![])];
+        let edited = vec![with_content(area("a", vec![]), "same")];
+
+        let merged = merge_areas(&base, &edited, &edited).unwrap();
+        assert_eq!(merged[0].content, "same");
+    }
+
+    #[test]
+    fn concurrent_edits_to_same_field_conflict() {
+        let base = vec![with_content(area("a", vec![]), "base")];
+        let theirs = vec![with_content(area("a", vec![]), "theirs")];
+        let yours = vec![with_content(area("a", vec![]), "yours")];
+
+        let conflicts = merge_areas(&base, &theirs, &yours).unwrap_err();
+        assert_eq!(conflict_fields(&conflicts), vec![("a", "content")]);
+        assert_eq!(conflicts[0].base, json!("base"));
+        assert_eq!(conflicts[0].theirs, json!("theirs"));
+        assert_eq!(conflicts[0].yours, json!("yours"));
+    }
+
+    #[test]
+    fn delete_of_unchanged_area_is_applied() {
+        let base = vec![area("a", vec![]), area("b", vec![])];
+        let deleted = vec![area("a", vec![])];
+
+        assert_eq!(shape(&merge_areas(&base, &deleted, &base).unwrap()), "a");
+        assert_eq!(shape(&merge_areas(&base, &base, &deleted).unwrap()), "a");
+    }
+
+    #[test]
+    fn deleting_parent_removes_unchanged_children() {
+        let base = vec![area("a", vec![area("b", vec![])]), area("c", vec![])];
+        let theirs = vec![area("c", vec![])];
+
+        let merged = merge_areas(&base, &theirs, &base).unwrap();
+        assert_eq!(shape(&merged), "c");
+    }
+
+    #[test]
+    fn delete_versus_edit_conflicts() {
+        let base = vec![area("a", vec![]), area("b", vec![])];
+        let deleted = vec![area("a", vec![])];
+        let edited = vec![area("a", vec![]), with_content(area("b", vec![]), "new")];
+
+        let conflicts = merge_areas(&base, &deleted, &edited).unwrap_err();
+        assert_eq!(conflict_fields(&conflicts), vec![("b", "area")]);
+        assert_eq!(conflicts[0].theirs, Value::Null);
+        assert_eq!(conflicts[0].yours["content"], json!("new"));
+
+        let conflicts = merge_areas(&base, &edited, &deleted).unwrap_err();
+        assert_eq!(conflict_fields(&conflicts), vec![("b", "area")]);
+        assert_eq!(conflicts[0].yours, Value::Null);
+    }
+
+    #[test]
+    fn adding_under_deleted_parent_conflicts_once() {
+        let base = vec![area("a", vec![]), area("d", vec![])];
+        let theirs = vec![area("d", vec![])];
+        let yours = vec![
+            area("a", vec![area("b", vec![]), area("c", vec![])]),
+            area("d", vec![]),
+        ];
+
+        let conflicts = merge_areas(&base, &theirs, &yours).unwrap_err();
+        assert_eq!(conflict_fields(&conflicts), vec![("a", "area")]);
+        assert_eq!(conflicts[0].theirs, Value::Null);
+    }
+
+    #[test]
+    fn conflicting_parent_is_not_reported_as_deleted() {
+        let base = vec![area("a", vec![area("b", vec![]), area("c", vec![])])];
+        let theirs = vec![with_content(
+            area("a", vec![area("b", vec![]), area("c", vec![])]),
+            "theirs",
+        )];
+        let yours = vec![with_content(
+            area("a", vec![area("b", vec![]), area("c", vec![])]),
+            "yours",
+        )];
+
+        let conflicts = merge_areas(&base, &theirs, &yours).unwrap_err();
+        assert_eq!(conflict_fields(&conflicts), vec![("a", "content")]);
+    }
+
+    #[test]
+    fn additions_from_both_sides_are_kept() {
+        let base = vec![area("a", vec![])];
+        let theirs = vec![area("a", vec![]), area("b", vec![])];
+        let yours = vec![area("a", vec![area("c", vec![])])];
+
+        let merged = merge_areas(&base, &theirs, &yours).unwrap();
+        assert_eq!(shape(&merged), "a[c],b");
+    }
+
+    #[test]
+    fn reorder_by_one_side_is_kept() {
+        let base = vec![area("a", vec![]), area("b", vec![]), area("c", vec![])];
+        let theirs = vec![
+            area("a", vec![]),
+            with_content(area("b", vec![]), "edited"),
+            area("c", vec![]),
+        ];
+        let yours = vec![area("c", vec![]), area("a", vec![]), area("b", vec![])];
+
+        let merged = merge_areas(&base, &theirs, &yours).unwrap();
+        assert_eq!(shape(&merged), "c,a,b");
+        assert_eq!(merged[2].content, "edited");
+    }
+
+    #[test]
+    fn addition_is_placed_after_its_previous_sibling_in_reordered_list() {
+        let base = vec![area("a", vec![]), area("b", vec![])];
+        let theirs = vec![area("a", vec![]), area("b", vec![]), area("c", vec![])];
+        let yours = vec![area("b", vec![]), area("a", vec![])];
+
+        let merged = merge_areas(&base, &theirs, &yours).unwrap();
+        assert_eq!(shape(&merged), "b,c,a");
+    }
+
+    #[test]
+    fn move_and_edit_are_combined() {
+        let base = vec![area("a", vec![]), area("b", vec![area("c", vec![])])];
+        let theirs = vec![area("a", vec![area("c", vec![])]), area("b", vec![])];
+        let yours = vec![
+            area("a", vec![]),
+            area("b", vec![with_content(area("c", vec![]), "moved")]),
+        ];
+
+        let merged = merge_areas(&base, &theirs, &yours).unwrap();
+        assert_eq!(shape(&merged), "a[c],b");
+        assert_eq!(merged[0].items[0].content, "moved");
+    }
+
+    #[test]
+    fn concurrent_moves_to_different_parents_conflict() {
+        let base = vec![area("a", vec![]), area("b", vec![]), area("c", vec![])];
+        let theirs = vec![area("a", vec![area("c", vec![])]), area("b", vec![])];
+        let yours = vec![area("a", vec![]), area("b", vec![area("c", vec![])])];
+
+        let conflicts = merge_areas(&base, &theirs, &yours).unwrap_err();
+        assert_eq!(conflict_fields(&conflicts), vec![("c", "parent")]);
+        assert_eq!(conflicts[0].theirs, json!("a"));
+        assert_eq!(conflicts[0].yours, json!("b"));
+    }
+
+    #[test]
+    fn moves_forming_a_cycle_conflict() {
+        let base = vec![area("a", vec![]), area("b", vec![])];
+        let theirs = vec![area("a", vec![area("b", vec![])])];
+        let yours = vec![area("b", vec![area("a", vec![])])];
+
+        let conflicts = merge_areas(&base, &theirs, &yours).unwrap_err();
+        assert!(!conflicts.is_empty());
+        assert!(conflicts.iter().all(|c| c.field == "parent"));
+    }
+}
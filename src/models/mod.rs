@@ -1,7 +1,13 @@
+pub mod api_token;
 pub mod error;
 pub mod house;
+pub mod house_invitation;
+pub mod image;
+pub mod invite;
+pub mod merge;
+pub mod password_reset;
+pub mod session;
+pub mod template;
+pub mod totp;
+pub mod trash;
 pub mod user;
-
-
-
-
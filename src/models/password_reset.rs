@@ -0,0 +1,52 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+// 重置码有效期（小时）
+const RESET_CODE_TTL_HOURS: i64 = 24;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PasswordResetCode {
+    pub code_hash: String,
+    pub user_id: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChangePasswordForm {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RedeemResetCodeForm {
+    pub code: String,
+    pub new_password: String,
+}
+
+impl PasswordResetCode {
+    // 返回新建的重置码以及只展示一次的明文
+    pub fn new(user_id: &str, created_by: &str) -> (Self, String) {
+        let code = Uuid::new_v4().simple().to_string();
+        let now = Utc::now();
+        let reset = Self {
+            code_hash: Self::hash(&code),
+            user_id: user_id.to_string(),
+            created_by: created_by.to_string(),
+            created_at: now,
+            expires_at: now + Duration::hours(RESET_CODE_TTL_HOURS),
+        };
+        (reset, code)
+    }
+
+    pub fn hash(code: &str) -> String {
+        format!("{:x}", Sha256::digest(code.as_bytes()))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
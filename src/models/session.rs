@@ -0,0 +1,60 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// 会话默认有效期（天），可通过环境变量 HOUSE_KEEPER_SESSION_TTL_DAYS 配置
+const DEFAULT_SESSION_TTL_DAYS: i64 = 30;
+
+// 最近访问时间的刷新间隔（秒），避免每个请求都写 session.json
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    pub token: String,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Session {
+    pub fn new(user_id: &str, user_agent: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            token: Uuid::new_v4().to_string(),
+            user_agent,
+            created_at: now,
+            last_seen_at: now,
+            expires_at: now + session_ttl(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+
+    // 刷新最近访问时间并顺延过期时间，返回是否需要持久化
+    pub fn touch(&mut self) -> bool {
+        let now = Utc::now();
+        if now - self.last_seen_at < Duration::seconds(TOUCH_INTERVAL_SECS) {
+            return false;
+        }
+        self.last_seen_at = now;
+        self.expires_at = now + session_ttl();
+        true
+    }
+}
+
+// 会话在无访问的情况下可以保持有效的时长
+pub fn session_ttl() -> Duration {
+    std::env::var("HOUSE_KEEPER_SESSION_TTL_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .and_then(Duration::try_days)
+        .unwrap_or_else(|| Duration::days(DEFAULT_SESSION_TTL_DAYS))
+}
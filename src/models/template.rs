@@ -0,0 +1,37 @@
+use crate::models::house::{CopyMode, HouseArea};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// 房屋模板，保存时已按 mode 复制好区域结构，只有创建人自己可以使用
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HouseTemplate {
+    pub id: String,
+    pub name: String,
+    pub owner: String,
+    pub mode: CopyMode,
+    pub created_at: DateTime<Utc>,
+    pub items: Vec<HouseArea>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaveTemplateForm {
+    // 模板名称，不填时使用房屋名称
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub mode: CopyMode,
+}
+
+impl HouseTemplate {
+    pub fn new(name: &str, owner: &str, mode: CopyMode, items: &[HouseArea]) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            owner: owner.to_string(),
+            mode,
+            created_at: Utc::now(),
+            items: items.iter().map(|a| a.duplicate(mode)).collect(),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base32::Alphabet;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+// RFC 6238 默认参数：30 秒一个时间步长，6 位数字
+const TIME_STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+// 允许前后各一个时间步长的时钟误差
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+const SECRET_BYTES: usize = 20;
+const RECOVERY_CODE_COUNT: usize = 8;
+const ISSUER: &str = "HouseKeeper";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TotpSettings {
+    // Base32 编码的共享密钥
+    pub secret: String,
+    // 首次校验通过后才启用
+    pub enabled: bool,
+    // 恢复码的 SHA-256 哈希，每个只能使用一次
+    pub recovery_codes: Vec<String>,
+    // 最近一次使用的时间步长，防止同一验证码被重复使用
+    pub last_used_step: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TotpCodeForm {
+    pub code: String,
+}
+
+impl TotpSettings {
+    // 返回新的配置以及只展示一次的明文恢复码
+    pub fn new() -> (Self, Vec<String>) {
+        let mut secret = [0u8; SECRET_BYTES];
+        OsRng.fill_bytes(&mut secret);
+
+        let recovery_codes = (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                let mut bytes = [0u8; 5];
+                OsRng.fill_bytes(&mut bytes);
+                let code = base32::encode(Alphabet::Rfc4648Lower { padding: false }, &bytes);
+                format!("{}-{}", &code[..4], &code[4..])
+            })
+            .collect::<Vec<_>>();
+
+        let settings = Self {
+            secret: base32::encode(Alphabet::Rfc4648 { padding: false }, &secret),
+            enabled: false,
+            recovery_codes: recovery_codes
+                .iter()
+                .map(|c| hash_recovery_code(c))
+                .collect(),
+            last_used_step: None,
+        };
+        (settings, recovery_codes)
+    }
+
+    pub fn otpauth_uri(&self, username: &str) -> String {
+        format!(
+            "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+            issuer = ISSUER,
+            account = percent_encode(username),
+            secret = self.secret,
+            digits = DIGITS,
+            period = TIME_STEP_SECS,
+        )
+    }
+
+    // 校验动态验证码，通过后记录时间步长
+    pub fn verify_code(&mut self, code: &str) -> bool {
+        let code = code.trim();
+        if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return false;
+        }
+        let secret = match base32::decode(Alphabet::Rfc4648 { padding: false }, &self.secret) {
+            Some(secret) => secret,
+            None => return false,
+        };
+
+        let current_step = Utc::now().timestamp() / TIME_STEP_SECS;
+        for step in current_step - ALLOWED_DRIFT_STEPS..=current_step + ALLOWED_DRIFT_STEPS {
+            if self.last_used_step.is_some_and(|last| step <= last) {
+                continue;
+            }
+            let expected = format!(
+                "{:0width$}",
+                hotp(&secret, step as u64),
+                width = DIGITS as usize
+            );
+            if constant_time_eq(expected.as_bytes(), code.as_bytes()) {
+                self.last_used_step = Some(step);
+                return true;
+            }
+        }
+        false
+    }
+
+    // 校验恢复码，通过后将其作废
+    pub fn use_recovery_code(&mut self, code: &str) -> bool {
+        let hash = hash_recovery_code(&code.trim().to_lowercase());
+        match self.recovery_codes.iter().position(|c| *c == hash) {
+            Some(index) => {
+                self.recovery_codes.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 依次尝试动态验证码和恢复码
+    pub fn verify_code_or_recovery(&mut self, code: &str) -> bool {
+        self.verify_code(code) || self.use_recovery_code(code)
+    }
+}
+
+// RFC 4226 HOTP
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    binary % 10u32.pow(DIGITS)
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    format!("{:x}", Sha256::digest(code.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,40 @@
+use crate::models::house::House;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+// 回收站中房屋的默认保留天数
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+// 被删除的房屋，详细数据文件 house/{id}.json 保留到彻底清除为止
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrashedHouse {
+    #[serde(flatten)]
+    pub house: House,
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: String,
+}
+
+impl TrashedHouse {
+    pub fn new(house: House, deleted_by: &str) -> Self {
+        Self {
+            house,
+            deleted_at: Utc::now(),
+            deleted_by: deleted_by.to_string(),
+        }
+    }
+
+    // 超过这个时间后会被彻底清除
+    pub fn purge_at(&self) -> DateTime<Utc> {
+        self.deleted_at + trash_retention()
+    }
+}
+
+// 删除的房屋在回收站中保留的时长
+pub fn trash_retention() -> Duration {
+    std::env::var("HOUSE_KEEPER_TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .and_then(Duration::try_days)
+        .unwrap_or_else(|| Duration::days(DEFAULT_TRASH_RETENTION_DAYS))
+}
@@ -1,3 +1,4 @@
+use crate::models::totp::TotpSettings;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -6,7 +7,34 @@ pub struct User {
     pub id: String,
     pub username: String,
     pub password: String,
-    pub token: String,
+    #[serde(default)]
+    pub is_admin: bool,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp: Option<TotpSettings>,
+    // 显示名称，未设置时前端展示用户名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    // 头像，保存 /api/images 上传后返回的文件名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateUserForm {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub is_admin: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateProfileForm {
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub avatar: Option<String>,
 }
 
 impl User {
@@ -15,7 +43,15 @@ impl User {
             id: Uuid::new_v4().to_string(),
             username: username.to_string(),
             password: password.to_string(),
-            token: Uuid::new_v4().to_string(),
+            is_admin: false,
+            disabled: false,
+            totp: None,
+            display_name: None,
+            avatar: None,
         }
     }
+
+    pub fn totp_enabled(&self) -> bool {
+        self.totp.as_ref().is_some_and(|t| t.enabled)
+    }
 }
@@ -0,0 +1,413 @@
+use crate::{
+    models::{
+        api_token::ApiToken,
+        error::AppError,
+        house::House,
+        house_invitation::HouseInvitation,
+        invite::InviteCode,
+        password_reset::{ChangePasswordForm, PasswordResetCode, RedeemResetCodeForm},
+        template::HouseTemplate,
+        totp::{TotpCodeForm, TotpSettings},
+        user::{UpdateProfileForm, User},
+    },
+    routes::auth::{auth_filter, revoke_sessions},
+    storage::{file_storage::FileStorage, trash::purge_houses},
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+#[derive(Debug, Deserialize)]
+struct DeleteAccountForm {
+    password: String,
+    // 房屋 ID -> 新的创建人（必须是该房屋的成员）
+    #[serde(default)]
+    transfers: HashMap<String, String>,
+}
+
+// 显示名称的最大长度（字符数）
+const MAX_DISPLAY_NAME_LENGTH: usize = 64;
+
+pub fn account_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let change_password = warp::path!("me" / "password")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(warp::cookie::optional("token"))
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(change_password_handler);
+
+    let redeem_reset_code = warp::path!("password-reset")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_storage(storage.clone()))
+        .and_then(redeem_reset_code_handler);
+
+    let enroll_totp = warp::path!("me" / "totp")
+        .and(warp::post())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(enroll_totp_handler);
+
+    let verify_totp = warp::path!("me" / "totp" / "verify")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(verify_totp_handler);
+
+    let disable_totp = warp::path!("me" / "totp")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(disable_totp_handler);
+
+    let delete_account = warp::path!("me")
+        .and(warp::delete())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(delete_account_handler);
+
+    let get_profile = warp::path!("me")
+        .and(warp::get())
+        .and(auth_filter(storage.clone()))
+        .and_then(get_profile_handler);
+
+    let update_profile = warp::path!("me" / "profile")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(update_profile_handler);
+
+    change_password
+        .or(get_profile)
+        .or(update_profile)
+        .or(delete_account)
+        .or(redeem_reset_code)
+        .or(enroll_totp)
+        .or(verify_totp)
+        .or(disable_totp)
+}
+
+async fn change_password_handler(
+    form: ChangePasswordForm,
+    token: Option<String>,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    if form.new_password.is_empty() {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
+    // 必须先校验当前密码
+    if !storage
+        .verify_password(&form.current_password, &user.password)
+        .unwrap_or(false)
+    {
+        return Err(warp::reject::custom(AppError::PasswordError));
+    }
+
+    set_password(&storage, &user.id, &form.new_password)?;
+
+    // 保留当前会话，其他设备需要重新登录
+    revoke_sessions(&storage, &user.id, token.as_deref()).map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Password changed successfully"
+    })))
+}
+
+async fn get_profile_handler(user: User) -> Result<impl warp::Reply, Rejection> {
+    Ok(warp::reply::json(&serde_json::json!({
+        "id": user.id,
+        "username": user.username,
+        "display_name": user.display_name,
+        "avatar": user.avatar,
+        "is_admin": user.is_admin,
+        "totp_enabled": user.totp_enabled(),
+    })))
+}
+
+async fn update_profile_handler(
+    form: UpdateProfileForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    // 空字符串视为清除
+    let display_name = form
+        .display_name
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+    if display_name
+        .as_ref()
+        .is_some_and(|d| d.chars().count() > MAX_DISPLAY_NAME_LENGTH)
+    {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
+    // 头像必须是已上传的图片文件名
+    let avatar = form.avatar.filter(|a| !a.is_empty());
+    if let Some(avatar) = &avatar {
+        if avatar.contains(['/', '\\']) || avatar.starts_with('.') {
+            return Err(warp::reject::custom(AppError::ParameterError));
+        }
+        storage
+            .file_size(&format!("images/{}", avatar))
+            .map_err(|_| warp::reject::custom(AppError::NotFound))?;
+    }
+
+    update_user(&storage, &user.id, |u| {
+        u.display_name = display_name;
+        u.avatar = avatar;
+    })?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Profile updated successfully"
+    })))
+}
+
+async fn redeem_reset_code_handler(
+    form: RedeemResetCodeForm,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    if form.new_password.is_empty() {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
+    // 重置码只能使用一次，在锁内取出
+    let code_hash = PasswordResetCode::hash(form.code.trim());
+    let reset = storage
+        .update_json(
+            "password_reset.json",
+            |resets: &mut Vec<PasswordResetCode>| {
+                resets.retain(|r| !r.is_expired());
+                let index = resets
+                    .iter()
+                    .position(|r| r.code_hash == code_hash)
+                    .ok_or(AppError::InvalidResetCode)?;
+                Ok(resets.remove(index))
+            },
+        )
+        .map_err(warp::reject::custom)?;
+
+    set_password(&storage, &reset.user_id, &form.new_password)?;
+    revoke_sessions(&storage, &reset.user_id, None).map_err(warp::reject::custom)?;
+    log::info!("Password of user {} reset by reset code", reset.user_id);
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Password reset successfully"
+    })))
+}
+
+async fn enroll_totp_handler(
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    // 已启用时需要先停用才能重新绑定
+    if user.totp_enabled() {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
+    let (totp, recovery_codes) = TotpSettings::new();
+    let otpauth_uri = totp.otpauth_uri(&user.username);
+    let secret = totp.secret.clone();
+    update_user(&storage, &user.id, |u| u.totp = Some(totp))?;
+
+    // 恢复码只在绑定时返回一次
+    Ok(warp::reply::json(&serde_json::json!({
+        "otpauth_uri": otpauth_uri,
+        "secret": secret,
+        "recovery_codes": recovery_codes,
+    })))
+}
+
+async fn verify_totp_handler(
+    form: TotpCodeForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let mut totp = user
+        .totp
+        .ok_or_else(|| warp::reject::custom(AppError::ParameterError))?;
+    if !totp.verify_code(&form.code) {
+        return Err(warp::reject::custom(AppError::InvalidSecondFactor));
+    }
+
+    // 首次校验通过后启用两步验证
+    totp.enabled = true;
+    update_user(&storage, &user.id, |u| u.totp = Some(totp))?;
+    log::info!(
+        "Two-factor authentication enabled for user {}",
+        user.username
+    );
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Two-factor authentication enabled"
+    })))
+}
+
+async fn disable_totp_handler(
+    form: TotpCodeForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let mut totp = user
+        .totp
+        .ok_or_else(|| warp::reject::custom(AppError::ParameterError))?;
+
+    // 已启用时停用需要提供有效的验证码或恢复码
+    if totp.enabled && !totp.verify_code_or_recovery(&form.code) {
+        return Err(warp::reject::custom(AppError::InvalidSecondFactor));
+    }
+
+    update_user(&storage, &user.id, |u| u.totp = None)?;
+    log::info!(
+        "Two-factor authentication disabled for user {}",
+        user.username
+    );
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Two-factor authentication disabled"
+    })))
+}
+
+async fn delete_account_handler(
+    form: DeleteAccountForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    if !storage
+        .verify_password(&form.password, &user.password)
+        .unwrap_or(false)
+    {
+        return Err(warp::reject::custom(AppError::PasswordError));
+    }
+
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            // 自己创建的房屋必须全部转让给成员，否则需要先删除
+            let mut unresolved = Vec::new();
+            for house in houses.iter_mut().filter(|h| h.creator == user.id) {
+                match form.transfers.get(&house.id) {
+                    Some(new_owner) if house.members.iter().any(|m| m.user_id == *new_owner) => {
+                        house.transfer_to(new_owner, None);
+                    }
+                    _ => unresolved.push(house.id.clone()),
+                }
+            }
+            if !unresolved.is_empty() {
+                return Err(AppError::HousesStillOwned(unresolved));
+            }
+
+            // 从所有房屋的成员列表中移除，并撤销发给自己的创建人转让请求
+            for house in houses.iter_mut() {
+                house.members.retain(|m| m.user_id != user.id);
+                if house
+                    .pending_transfer
+                    .as_ref()
+                    .is_some_and(|t| t.to == user.id)
+                {
+                    house.pending_transfer = None;
+                }
+            }
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    // 清理会话、访问令牌、邀请码、房屋邀请和密码重置码
+    revoke_sessions(&storage, &user.id, None).map_err(warp::reject::custom)?;
+    storage
+        .update_json("api_token.json", |tokens: &mut Vec<ApiToken>| {
+            tokens.retain(|t| t.user_id != user.id);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+    storage
+        .update_json("invite.json", |invites: &mut Vec<InviteCode>| {
+            invites.retain(|i| i.creator != user.id);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+    storage
+        .update_json(
+            "house_invitation.json",
+            |invitations: &mut Vec<HouseInvitation>| {
+                invitations
+                    .retain(|i| i.inviter != user.id && i.invitee.as_deref() != Some(&user.id));
+                Ok(())
+            },
+        )
+        .map_err(warp::reject::custom)?;
+    storage
+        .update_json(
+            "password_reset.json",
+            |resets: &mut Vec<PasswordResetCode>| {
+                resets.retain(|r| r.user_id != user.id);
+                Ok(())
+            },
+        )
+        .map_err(warp::reject::custom)?;
+
+    storage
+        .update_json("template.json", |templates: &mut Vec<HouseTemplate>| {
+            templates.retain(|t| t.owner != user.id);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    // 回收站中自己创建的房屋直接彻底清除
+    purge_houses(&storage, |t| t.house.creator == user.id).map_err(warp::reject::custom)?;
+
+    storage
+        .update_json("user.json", |users: &mut Vec<User>| {
+            users.retain(|u| u.id != user.id);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+    log::info!("Deleted account of user {}", user.username);
+
+    Ok(warp::reply::with_header(
+        warp::reply::json(&serde_json::json!({
+            "message": "Account deleted successfully"
+        })),
+        "Set-Cookie",
+        "token=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0",
+    ))
+}
+
+fn set_password(storage: &FileStorage, user_id: &str, password: &str) -> Result<(), Rejection> {
+    let password = storage
+        .hash_password(password)
+        .map_err(warp::reject::custom)?;
+    update_user(storage, user_id, |u| u.password = password)
+}
+
+// 在锁内读取 user.json，修改指定用户后写回
+fn update_user(
+    storage: &FileStorage,
+    user_id: &str,
+    f: impl FnOnce(&mut User),
+) -> Result<(), Rejection> {
+    storage
+        .update_json("user.json", |users: &mut Vec<User>| {
+            let user = users
+                .iter_mut()
+                .find(|u| u.id == user_id)
+                .ok_or(AppError::UserNotFound)?;
+            f(user);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
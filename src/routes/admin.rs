@@ -0,0 +1,341 @@
+use crate::{
+    models::{
+        error::AppError,
+        house::House,
+        password_reset::PasswordResetCode,
+        user::{CreateUserForm, User},
+    },
+    routes::auth::{admin_filter, revoke_sessions},
+    storage::file_storage::FileStorage,
+};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+// 返回给管理员的用户信息，不包含密码
+#[derive(Debug, Serialize)]
+struct UserView {
+    id: String,
+    username: String,
+    is_admin: bool,
+    disabled: bool,
+}
+
+impl From<&User> for UserView {
+    fn from(u: &User) -> Self {
+        Self {
+            id: u.id.clone(),
+            username: u.username.clone(),
+            is_admin: u.is_admin,
+            disabled: u.disabled,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct HouseView {
+    id: String,
+    name: String,
+    creator: String,
+    creator_username: Option<String>,
+    orphaned: bool,
+    member_count: usize,
+    detail_size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetDisabledForm {
+    disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReassignOwnerForm {
+    user_id: String,
+}
+
+pub fn admin_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let list_users = warp::path!("admin" / "users")
+        .and(warp::get())
+        .and(admin_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(list_users_handler);
+
+    let create_user = warp::path!("admin" / "users")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(admin_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(create_user_handler);
+
+    let set_user_disabled = warp::path!("admin" / "users" / String / "disabled")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(admin_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(set_user_disabled_handler);
+
+    let revoke_user_sessions = warp::path!("admin" / "users" / String / "sessions")
+        .and(warp::delete())
+        .and(admin_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(revoke_user_sessions_handler);
+
+    let create_reset_code = warp::path!("admin" / "users" / String / "password-reset")
+        .and(warp::post())
+        .and(admin_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(create_reset_code_handler);
+
+    let list_houses = warp::path!("admin" / "houses")
+        .and(warp::get())
+        .and(admin_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(list_houses_handler);
+
+    let reassign_owner = warp::path!("admin" / "houses" / String / "owner")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(admin_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(reassign_owner_handler);
+
+    list_users
+        .or(create_user)
+        .or(set_user_disabled)
+        .or(revoke_user_sessions)
+        .or(create_reset_code)
+        .or(list_houses)
+        .or(reassign_owner)
+}
+
+async fn list_users_handler(
+    _admin: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+
+    let views = users.iter().map(UserView::from).collect::<Vec<_>>();
+    Ok(warp::reply::json(&views))
+}
+
+async fn create_user_handler(
+    form: CreateUserForm,
+    admin: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let username = form.username.trim();
+    if username.is_empty() || form.password.is_empty() {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
+    // 先检查一次用户名，避免无谓地计算密码哈希
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+    if users.iter().any(|u| u.username == username) {
+        return Err(warp::reject::custom(AppError::UsernameTaken));
+    }
+
+    let password = storage
+        .hash_password(&form.password)
+        .map_err(warp::reject::custom)?;
+    let mut user = User::new(username, &password);
+    user.is_admin = form.is_admin;
+    let view = UserView::from(&user);
+
+    // 在锁内再次检查用户名，避免并发创建同名用户
+    storage
+        .update_json("user.json", |users: &mut Vec<User>| {
+            if users.iter().any(|u| u.username == username) {
+                return Err(AppError::UsernameTaken);
+            }
+            users.push(user);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+    log::info!("Admin {} created user {}", admin.id, username);
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&view),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+async fn set_user_disabled_handler(
+    user_id: String,
+    form: SetDisabledForm,
+    admin: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    // 避免管理员把自己锁在系统外
+    if user_id == admin.id {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
+    let view = storage
+        .update_json("user.json", |users: &mut Vec<User>| {
+            let user = users
+                .iter_mut()
+                .find(|u| u.id == user_id)
+                .ok_or(AppError::UserNotFound)?;
+            user.disabled = form.disabled;
+            Ok(UserView::from(&*user))
+        })
+        .map_err(warp::reject::custom)?;
+
+    // 禁用账号时同时让其所有会话失效
+    if form.disabled {
+        revoke_sessions(&storage, &user_id, None).map_err(warp::reject::custom)?;
+    }
+    log::info!(
+        "Admin {} set disabled={} for user {}",
+        admin.id,
+        form.disabled,
+        user_id
+    );
+
+    Ok(warp::reply::json(&view))
+}
+
+async fn revoke_user_sessions_handler(
+    user_id: String,
+    admin: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    revoke_sessions(&storage, &user_id, None).map_err(warp::reject::custom)?;
+    log::info!(
+        "Admin {} revoked all sessions of user {}",
+        admin.id,
+        user_id
+    );
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "All sessions revoked successfully"
+    })))
+}
+
+async fn create_reset_code_handler(
+    user_id: String,
+    admin: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+    if !users.iter().any(|u| u.id == user_id) {
+        return Err(warp::reject::custom(AppError::UserNotFound));
+    }
+
+    // 同一用户只保留最新的一个重置码
+    let (reset, code) = PasswordResetCode::new(&user_id, &admin.id);
+    let expires_at = reset.expires_at;
+    storage
+        .update_json(
+            "password_reset.json",
+            |resets: &mut Vec<PasswordResetCode>| {
+                resets.retain(|r| !r.is_expired() && r.user_id != user_id);
+                resets.push(reset);
+                Ok(())
+            },
+        )
+        .map_err(warp::reject::custom)?;
+    log::info!(
+        "Admin {} issued password reset code for user {}",
+        admin.id,
+        user_id
+    );
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "code": code,
+            "expires_at": expires_at,
+        })),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+async fn list_houses_handler(
+    _admin: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let houses: Vec<House> = storage
+        .read_json("house.json")
+        .map_err(warp::reject::custom)?;
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+
+    let views = houses
+        .into_iter()
+        .map(|house| {
+            let creator = users.iter().find(|u| u.id == house.creator);
+            HouseView {
+                detail_size: storage
+                    .file_size(&format!("house/{}.json", house.id))
+                    .unwrap_or(0),
+                // 创建人已不存在或已被禁用
+                orphaned: creator.is_none_or(|u| u.disabled),
+                creator_username: creator.map(|u| u.username.clone()),
+                member_count: house.members.len(),
+                id: house.id,
+                name: house.name,
+                creator: house.creator,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(warp::reply::json(&views))
+}
+
+async fn reassign_owner_handler(
+    house_id: String,
+    form: ReassignOwnerForm,
+    admin: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+    if !users.iter().any(|u| u.id == form.user_id) {
+        return Err(warp::reject::custom(AppError::UserNotFound));
+    }
+
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = houses
+                .iter_mut()
+                .find(|h| h.id == house_id)
+                .ok_or(AppError::HouseNotFound)?;
+
+            // 只允许重新指定创建人已不存在或已被禁用的房屋，被禁用的原创建人保留为普通成员
+            let former_username = match users.iter().find(|u| u.id == house.creator) {
+                Some(creator) if creator.disabled => Some(creator.username.clone()),
+                Some(_) => return Err(AppError::PermissionDenied),
+                None => None,
+            };
+
+            house.transfer_to(&form.user_id, former_username);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+    log::info!(
+        "Admin {} reassigned house {} to user {}",
+        admin.id,
+        house_id,
+        form.user_id
+    );
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "House owner reassigned successfully"
+    })))
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
@@ -0,0 +1,226 @@
+use crate::{
+    models::{
+        error::AppError,
+        house::{
+            CreateAreaForm, DeleteAreaQuery, House, HouseArea, HouseDetail, HouseRole,
+            MoveAreaForm, UpdateAreaForm,
+        },
+        user::User,
+    },
+    routes::{
+        auth::auth_filter,
+        houses::{require_role, require_writable},
+    },
+    storage::{file_storage::FileStorage, history::update_house_detail},
+};
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+// 按区域 ID 修改单个区域，服务端加锁完成读取-修改-写回，只有同一个区域被别人改过时才冲突
+pub fn area_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let create_area = warp::path!("houses" / String / "areas")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(create_area_handler);
+
+    let update_area = warp::path!("houses" / String / "areas" / String)
+        .and(warp::patch())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(update_area_handler);
+
+    let delete_area = warp::path!("houses" / String / "areas" / String)
+        .and(warp::delete())
+        .and(warp::query::<DeleteAreaQuery>())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(delete_area_handler);
+
+    let move_area = warp::path!("houses" / String / "areas" / String / "move")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(move_area_handler);
+
+    create_area.or(update_area).or(delete_area).or(move_area)
+}
+
+async fn create_area_handler(
+    house_id: String,
+    form: CreateAreaForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let name = form.name.trim();
+    if name.is_empty() {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+    check_editable(&storage, &house_id, &user)?;
+
+    let area = HouseArea::new(name, form.content, form.images);
+    let version = update_house_detail(&storage, &house_id, |detail: &mut HouseDetail| {
+        let siblings = detail
+            .children_mut(form.parent_id.as_deref())
+            .ok_or(AppError::AreaNotFound)?;
+        let position = form.position.unwrap_or(siblings.len()).min(siblings.len());
+        siblings.insert(position, area.clone());
+        Ok(detail.touch())
+    })
+    .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "area": area,
+            "version": version
+        })),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+async fn update_area_handler(
+    house_id: String,
+    area_id: String,
+    form: UpdateAreaForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let name = form.name.as_deref().map(str::trim);
+    if name == Some("") {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+    check_editable(&storage, &house_id, &user)?;
+
+    let (area, version) = update_house_detail(&storage, &house_id, |detail: &mut HouseDetail| {
+        let area = detail
+            .find_area_mut(&area_id)
+            .ok_or(AppError::AreaNotFound)?;
+        if area.version != form.version {
+            return Err(AppError::VersionMismatch);
+        }
+
+        // 只修改提交了的字段，子区域不受影响
+        if let Some(name) = name {
+            area.name = name.to_string();
+        }
+        if let Some(content) = form.content {
+            area.content = content;
+        }
+        if let Some(images) = form.images {
+            area.images = images;
+        }
+        area.touch();
+
+        let area = area.clone();
+        Ok((area, detail.touch()))
+    })
+    .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "area": area,
+        "version": version
+    })))
+}
+
+async fn delete_area_handler(
+    house_id: String,
+    area_id: String,
+    query: DeleteAreaQuery,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let expected = query
+        .version
+        .clone()
+        .ok_or_else(|| warp::reject::custom(AppError::ParameterError))?;
+    let expected_descendants = query
+        .descendant_versions()
+        .ok_or_else(|| warp::reject::custom(AppError::ParameterError))?;
+    check_editable(&storage, &house_id, &user)?;
+
+    let version = update_house_detail(&storage, &house_id, |detail: &mut HouseDetail| {
+        let area = detail
+            .find_area_mut(&area_id)
+            .ok_or(AppError::AreaNotFound)?;
+        // 子区域被别人修改、添加或移走时同样视为冲突，避免误删别人刚修改的内容
+        if area.version != expected || area.descendant_versions() != expected_descendants {
+            return Err(AppError::VersionMismatch);
+        }
+        // 连同所有子区域一起删除
+        detail.remove_area(&area_id);
+        Ok(detail.touch())
+    })
+    .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "version": version,
+        "message": "Area deleted successfully"
+    })))
+}
+
+async fn move_area_handler(
+    house_id: String,
+    area_id: String,
+    form: MoveAreaForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    check_editable(&storage, &house_id, &user)?;
+
+    let (area, version) = update_house_detail(&storage, &house_id, |detail: &mut HouseDetail| {
+        let area = detail
+            .find_area_mut(&area_id)
+            .ok_or(AppError::AreaNotFound)?;
+        if area.version != form.version {
+            return Err(AppError::VersionMismatch);
+        }
+        // 不能移动到自身或自己的子区域下
+        if let Some(parent_id) = &form.parent_id {
+            if area.contains(parent_id) {
+                return Err(AppError::ParameterError);
+            }
+        }
+        if detail.children_mut(form.parent_id.as_deref()).is_none() {
+            return Err(AppError::AreaNotFound);
+        }
+
+        let mut area = detail.remove_area(&area_id).ok_or(AppError::AreaNotFound)?;
+        area.touch();
+        let siblings = detail
+            .children_mut(form.parent_id.as_deref())
+            .ok_or(AppError::AreaNotFound)?;
+        let position = form.position.unwrap_or(siblings.len()).min(siblings.len());
+        siblings.insert(position, area.clone());
+        Ok((area, detail.touch()))
+    })
+    .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "area": area,
+        "version": version
+    })))
+}
+
+// editor 及以上角色才能修改区域，已归档的房屋不能修改
+fn check_editable(storage: &FileStorage, house_id: &str, user: &User) -> Result<(), Rejection> {
+    let houses: Vec<House> = storage
+        .read_json("house.json")
+        .map_err(warp::reject::custom)?;
+    let house = houses
+        .iter()
+        .find(|h| h.id == house_id)
+        .ok_or_else(|| warp::reject::custom(AppError::HouseNotFound))?;
+    require_role(house, user, HouseRole::Editor)?;
+    require_writable(house)
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
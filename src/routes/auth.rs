@@ -1,10 +1,24 @@
 use crate::{
-    models::{error::AppError, user::User},
-    storage::file_storage::FileStorage,
+    models::{
+        api_token::ApiToken,
+        error::AppError,
+        invite::InviteCode,
+        session::{session_ttl, Session},
+        user::User,
+    },
+    routes::proxy_auth::{client_ip, resolve_proxy_user},
+    storage::{
+        file_storage::FileStorage, login_challenge::LoginChallenges, login_throttle::LoginThrottle,
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{convert::Infallible, net::SocketAddr, sync::OnceLock};
+use warp::{
+    http::{HeaderMap, Method},
+    path::FullPath,
+    reject::Rejection,
+    Filter, Reply,
 };
-use serde::{Deserialize, Serialize};
-use std::convert::Infallible;
-use warp::{http::StatusCode, reject::Rejection, Filter, Reply};
 
 #[derive(Debug, Deserialize)]
 struct LoginRequest {
@@ -12,85 +26,491 @@ struct LoginRequest {
     password: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct RegisterRequest {
+    username: String,
+    password: String,
+    invite_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SecondFactorRequest {
+    challenge: String,
+    code: String,
+}
+
 #[derive(Debug, Serialize)]
 struct LoginResponse {
     username: String,
 }
 
+#[derive(Debug, Serialize)]
+struct SecondFactorChallenge {
+    second_factor_required: bool,
+    challenge: String,
+}
+
 pub fn auth_routes(
     file_storage: FileStorage,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let challenges = LoginChallenges::new();
+    // 密码和两步验证码共用失败计数，避免绕过锁定暴力尝试验证码
+    let throttle = LoginThrottle::new();
+
     let login = warp::path!("login")
         .and(warp::post())
         .and(json_body())
+        .and(warp::header::optional::<String>("user-agent"))
+        .and(warp::addr::remote())
+        .and(warp::header::headers_cloned())
         .and(with_storage(file_storage.clone()))
+        .and(with_throttle(throttle.clone()))
+        .and(with_challenges(challenges.clone()))
         .and_then(handle_login);
 
-    login
+    let login_second_factor = warp::path!("login" / "totp")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::addr::remote())
+        .and(warp::header::headers_cloned())
+        .and(with_storage(file_storage.clone()))
+        .and(with_throttle(throttle.clone()))
+        .and(with_challenges(challenges.clone()))
+        .and_then(handle_login_second_factor);
+
+    let register = warp::path!("register")
+        .and(warp::post())
+        .and(json_body())
+        .and(warp::header::optional::<String>("user-agent"))
+        .and(with_storage(file_storage.clone()))
+        .and_then(handle_register);
+
+    let logout = warp::path!("logout")
+        .and(warp::post())
+        .and(warp::cookie::optional("token"))
+        .and(with_storage(file_storage.clone()))
+        .and_then(handle_logout);
+
+    login.or(login_second_factor).or(register).or(logout)
 }
 
-async fn handle_login(req: LoginRequest, storage: FileStorage) -> Result<impl Reply, Rejection> {
-    let mut users: Vec<User> = storage.read_json("user.json").map_err(|e| {
+async fn handle_login(
+    req: LoginRequest,
+    user_agent: Option<String>,
+    remote: Option<SocketAddr>,
+    headers: HeaderMap,
+    storage: FileStorage,
+    throttle: LoginThrottle,
+    challenges: LoginChallenges,
+) -> Result<warp::reply::Response, Rejection> {
+    let throttle_keys = throttle_keys(&req.username, remote, &headers);
+    throttle.check(&throttle_keys)?;
+
+    let users: Vec<User> = storage.read_json("user.json").map_err(|e| {
         log::error!("Failed to read user.json: {}", e);
         AppError::FileSystemError(e.to_string())
     })?;
     log::info!("users count: {}", users.len());
 
-    let user_index = users
-        .iter()
-        .position(|u| u.username == req.username)
-        .ok_or_else(|| AppError::UserNotFound)?;
-
-    if !storage
-        .verify_password(&req.password, &users[user_index].password)
-        .map_err(|_| AppError::PasswordError)?
-    {
-        return Err(AppError::PasswordError.into());
+    // 用户名不存在时同样校验一次密码，保证响应耗时和错误信息与密码错误时一致
+    let user_index = users.iter().position(|u| u.username == req.username);
+    let stored_password = match user_index {
+        Some(index) => users[index].password.as_str(),
+        None => dummy_password_hash(&storage),
+    };
+    let verified = storage
+        .verify_password(&req.password, stored_password)
+        .unwrap_or(false);
+    let user_index = match user_index {
+        Some(index) if verified => index,
+        _ => {
+            throttle.record_failure(&throttle_keys);
+            return Err(AppError::PasswordError.into());
+        }
+    };
+
+    if users[user_index].disabled {
+        return Err(AppError::AccountDisabled.into());
     }
 
-    let new_token = uuid::Uuid::new_v4().to_string();
-    users[user_index].token = new_token.clone();
+    // 旧的明文密码在首次登录成功时自动迁移为 Argon2 哈希
+    if storage.needs_rehash(&users[user_index].password) {
+        log::info!("Migrating plaintext password of user {}", req.username);
+        let password = storage.hash_password(&req.password)?;
+        let user_id = &users[user_index].id;
+        let plaintext = &users[user_index].password;
+        // 在锁内只修改该用户的密码，期间密码已被修改过时不再覆盖
+        storage.update_json("user.json", |users: &mut Vec<User>| {
+            if let Some(user) = users
+                .iter_mut()
+                .find(|u| u.id == *user_id && u.password == *plaintext)
+            {
+                user.password = password;
+            }
+            Ok(())
+        })?;
+    }
 
-    storage
-        .write_json("user.json", &users)
-        .map_err(|e| AppError::FileSystemError(e.to_string()))?;
+    // 启用了两步验证时先不创建会话，也不清除失败计数，等待验证码校验通过
+    if users[user_index].totp_enabled() {
+        let challenge = challenges.create(&users[user_index].id, user_agent);
+        return Ok(warp::reply::json(&SecondFactorChallenge {
+            second_factor_required: true,
+            challenge,
+        })
+        .into_response());
+    }
+
+    throttle.record_success(&throttle_keys[0]);
+
+    // 每次登录创建独立的会话，不影响该用户在其他设备上的登录状态
+    let session = start_session(&storage, &users[user_index].id, user_agent)?;
 
-    let cookie = format!("token={}; Path=/; HttpOnly; Max-Age=2592000", new_token);
     Ok(warp::reply::with_header(
         warp::reply::json(&LoginResponse {
             username: req.username,
         }),
         "Set-Cookie",
-        cookie,
+        session_cookie(&session.token),
+    )
+    .into_response())
+}
+
+async fn handle_login_second_factor(
+    req: SecondFactorRequest,
+    remote: Option<SocketAddr>,
+    headers: HeaderMap,
+    storage: FileStorage,
+    throttle: LoginThrottle,
+    challenges: LoginChallenges,
+) -> Result<impl Reply, Rejection> {
+    let (user_id, user_agent) = challenges
+        .attempt(&req.challenge)
+        .ok_or(AppError::InvalidSecondFactor)?;
+
+    let users: Vec<User> = storage.read_json("user.json")?;
+    let username = users
+        .iter()
+        .find(|u| u.id == user_id && !u.disabled)
+        .map(|u| u.username.clone())
+        .ok_or(AppError::InvalidSecondFactor)?;
+
+    let throttle_keys = throttle_keys(&username, remote, &headers);
+    throttle.check(&throttle_keys)?;
+
+    // 在锁内校验并记录已使用的时间步长或作废已使用的恢复码，避免同一验证码被并发重复使用
+    let verified = storage.update_json("user.json", |users: &mut Vec<User>| {
+        let totp = users
+            .iter_mut()
+            .find(|u| u.id == user_id && !u.disabled)
+            .and_then(|u| u.totp.as_mut())
+            .filter(|t| t.enabled)
+            .ok_or(AppError::InvalidSecondFactor)?;
+        if totp.verify_code_or_recovery(&req.code) {
+            Ok(())
+        } else {
+            Err(AppError::InvalidSecondFactor)
+        }
+    });
+    if let Err(e) = verified {
+        if matches!(e, AppError::InvalidSecondFactor) {
+            log::warn!("Invalid second factor code for user {}", username);
+            throttle.record_failure(&throttle_keys);
+        }
+        return Err(e.into());
+    }
+    throttle.record_success(&throttle_keys[0]);
+    challenges.complete(&req.challenge);
+
+    let session = start_session(&storage, &user_id, user_agent)?;
+    Ok(warp::reply::with_header(
+        warp::reply::json(&LoginResponse { username }),
+        "Set-Cookie",
+        session_cookie(&session.token),
+    ))
+}
+
+async fn handle_logout(
+    token: Option<String>,
+    storage: FileStorage,
+) -> Result<impl Reply, Rejection> {
+    // 使当前会话失效，未登录时直接清除 Cookie 即可
+    if let Some(token) = token {
+        storage.update_json("session.json", |sessions: &mut Vec<Session>| {
+            sessions.retain(|s| s.token != token);
+            Ok(())
+        })?;
+    }
+
+    Ok(warp::reply::with_header(
+        warp::reply::json(&serde_json::json!({
+            "message": "Logged out successfully"
+        })),
+        "Set-Cookie",
+        "token=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0",
     ))
 }
 
-fn json_body() -> impl Filter<Extract = (LoginRequest,), Error = Rejection> + Clone {
+async fn handle_register(
+    req: RegisterRequest,
+    user_agent: Option<String>,
+    storage: FileStorage,
+) -> Result<impl Reply, Rejection> {
+    let username = req.username.trim();
+    if username.is_empty() || req.password.is_empty() {
+        return Err(AppError::ParameterError.into());
+    }
+
+    // 用户名不能重复，先检查一次以免无谓地消耗邀请码
+    let users: Vec<User> = storage.read_json("user.json")?;
+    if users.iter().any(|u| u.username == username) {
+        return Err(AppError::UsernameTaken.into());
+    }
+    let password = storage.hash_password(&req.password)?;
+
+    // 校验邀请码：必须存在且未过期，在锁内取出以保证只能使用一次
+    let invite = storage.update_json("invite.json", |invites: &mut Vec<InviteCode>| {
+        let index = invites
+            .iter()
+            .position(|i| i.code == req.invite_code && !i.is_expired())
+            .ok_or(AppError::InvalidInviteCode)?;
+        Ok(invites.remove(index))
+    })?;
+
+    // 在锁内再次检查用户名，避免并发注册创建同名用户
+    let created = storage.update_json("user.json", |users: &mut Vec<User>| {
+        if users.iter().any(|u| u.username == username) {
+            return Err(AppError::UsernameTaken);
+        }
+        let user = User::new(username, &password);
+        let user_id = user.id.clone();
+        users.push(user);
+        Ok(user_id)
+    });
+    let user_id = match created {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            // 用户创建失败时归还邀请码
+            if let Err(restore_err) =
+                storage.update_json("invite.json", |invites: &mut Vec<InviteCode>| {
+                    invites.push(invite.clone());
+                    Ok(())
+                })
+            {
+                log::error!("Failed to restore invite code: {}", restore_err);
+            }
+            return Err(e.into());
+        }
+    };
+    log::info!(
+        "Registered user {} with invite code from {}",
+        username,
+        invite.creator
+    );
+
+    let session = start_session(&storage, &user_id, user_agent)?;
+    Ok(warp::reply::with_status(
+        warp::reply::with_header(
+            warp::reply::json(&LoginResponse {
+                username: username.to_string(),
+            }),
+            "Set-Cookie",
+            session_cookie(&session.token),
+        ),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+fn json_body<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+{
     warp::body::content_length_limit(1024 * 16).and(warp::body::json())
 }
 
+// 分别按用户名和来源 IP 限制失败次数，第一个 key 为用户名
+fn throttle_keys(username: &str, remote: Option<SocketAddr>, headers: &HeaderMap) -> Vec<String> {
+    let mut keys = vec![format!("user:{}", username)];
+    if let Some(ip) = client_ip(remote, headers) {
+        keys.push(format!("ip:{}", ip));
+    }
+    keys
+}
+
+fn with_challenges(
+    challenges: LoginChallenges,
+) -> impl Filter<Extract = (LoginChallenges,), Error = Infallible> + Clone {
+    warp::any().map(move || challenges.clone())
+}
+
+fn with_throttle(
+    throttle: LoginThrottle,
+) -> impl Filter<Extract = (LoginThrottle,), Error = Infallible> + Clone {
+    warp::any().map(move || throttle.clone())
+}
+
+// 用于用户名不存在时的密码校验，首次使用时生成
+fn dummy_password_hash(storage: &FileStorage) -> &'static str {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    DUMMY_HASH.get_or_init(|| {
+        storage
+            .hash_password(&uuid::Uuid::new_v4().to_string())
+            .unwrap_or_default()
+    })
+}
+
 fn with_storage(
     storage: FileStorage,
 ) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
     warp::any().map(move || storage.clone())
 }
 
+// 创建新会话并写入 session.json，同时清理已过期的会话
+pub fn start_session(
+    storage: &FileStorage,
+    user_id: &str,
+    user_agent: Option<String>,
+) -> Result<Session, AppError> {
+    let session = Session::new(user_id, user_agent);
+    storage.update_json("session.json", |sessions: &mut Vec<Session>| {
+        sessions.retain(|s| !s.is_expired());
+        sessions.push(session.clone());
+        Ok(())
+    })?;
+    Ok(session)
+}
+
+// 删除该用户的所有会话，keep_token 对应的当前会话除外
+pub fn revoke_sessions(
+    storage: &FileStorage,
+    user_id: &str,
+    keep_token: Option<&str>,
+) -> Result<(), AppError> {
+    storage.update_json("session.json", |sessions: &mut Vec<Session>| {
+        sessions.retain(|s| s.user_id != user_id || Some(s.token.as_str()) == keep_token);
+        Ok(())
+    })
+}
+
+// Cookie 本身至少保留 30 天，是否过期以服务端会话的滑动有效期为准
+pub fn session_cookie(token: &str) -> String {
+    let max_age = session_ttl().max(chrono::Duration::days(30));
+    format!(
+        "token={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}",
+        token,
+        max_age.num_seconds()
+    )
+}
+
+// 根据 token 查找有效会话对应的用户
+fn resolve_session_user(storage: &FileStorage, token: &str) -> Result<User, AppError> {
+    let (mut session, user) = storage
+        .with_auth_index(|index| index.session_user(token))
+        .map_err(|_| AppError::AuthenticationRequired)?
+        .ok_or(AppError::AuthenticationRequired)?;
+
+    // 最近访问时间需要刷新时才读写 session.json，在锁内修改，避免恢复刚被撤销的会话
+    if session.touch() {
+        let result = storage.update_json("session.json", |sessions: &mut Vec<Session>| {
+            if let Some(s) = sessions.iter_mut().find(|s| s.token == token) {
+                s.touch();
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to update session last seen time: {}", e);
+        }
+    }
+
+    Ok(user)
+}
+
+// 根据 Bearer token 查找个人访问令牌及其所属用户
+fn resolve_api_token_user(
+    storage: &FileStorage,
+    secret: &str,
+) -> Result<(User, ApiToken), AppError> {
+    let token_hash = ApiToken::hash(secret);
+    let (mut api_token, user) = storage
+        .with_auth_index(|index| index.api_token_user(&token_hash))
+        .map_err(|_| AppError::AuthenticationRequired)?
+        .ok_or(AppError::AuthenticationRequired)?;
+
+    // 最近使用时间需要刷新时才读写 api_token.json，在锁内修改，避免恢复刚被撤销的令牌
+    if api_token.touch() {
+        let result = storage.update_json("api_token.json", |tokens: &mut Vec<ApiToken>| {
+            if let Some(t) = tokens.iter_mut().find(|t| t.token_hash == token_hash) {
+                t.touch();
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to update api token last used time: {}", e);
+        }
+    }
+
+    Ok((user, api_token))
+}
+
 // 认证中间件
 pub fn auth_filter(
     storage: FileStorage,
 ) -> impl Filter<Extract = (User,), Error = Rejection> + Clone {
+    auth_with_token_filter(storage).map(|user: User, _: Option<ApiToken>| user)
+}
+
+// 管理员认证中间件
+pub fn admin_filter(
+    storage: FileStorage,
+) -> impl Filter<Extract = (User,), Error = Rejection> + Clone {
+    auth_filter(storage).and_then(|user: User| async move {
+        if user.is_admin {
+            Ok(user)
+        } else {
+            Err(warp::reject::custom(AppError::PermissionDenied))
+        }
+    })
+}
+
+// 认证中间件，同时返回本次请求使用的个人访问令牌（Cookie 登录时为 None）
+pub fn auth_with_token_filter(
+    storage: FileStorage,
+) -> impl Filter<Extract = (User, Option<ApiToken>), Error = Rejection> + Clone {
     warp::any()
+        .and(warp::header::headers_cloned())
         .and(warp::cookie::optional("token"))
+        .and(warp::addr::remote())
+        .and(warp::method())
+        .and(warp::path::full())
         .and(with_storage(storage))
-        .and_then(|token: Option<String>, storage: FileStorage| async move {
-            let token = token.ok_or(AppError::AuthenticationRequired)?;
-            let users: Vec<User> = storage
-                .read_json("user.json")
-                .map_err(|_| AppError::AuthenticationRequired)?;
-            users
-                .into_iter()
-                .find(|u| u.token == token)
-                .ok_or_else(|| AppError::AuthenticationRequired)
-                .map_err(|e: AppError| -> Rejection { e.into() })
-        })
+        .and_then(
+            |headers: HeaderMap,
+             token: Option<String>,
+             remote: Option<SocketAddr>,
+             method: Method,
+             path: FullPath,
+             storage: FileStorage| async move {
+                // 优先使用 Authorization: Bearer 头中的个人访问令牌
+                if let Some(secret) = headers
+                    .get("authorization")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.strip_prefix("Bearer "))
+                {
+                    let (user, api_token) = resolve_api_token_user(&storage, secret.trim())?;
+                    if !api_token.permits(&method, path.as_str()) {
+                        return Err(AppError::PermissionDenied.into());
+                    }
+                    return Ok((user, Some(api_token)));
+                }
+
+                // 启用反向代理认证时，信任代理传递的用户名
+                if let Some(user) = resolve_proxy_user(&storage, remote, &headers)? {
+                    return Ok((user, None));
+                }
+
+                let token = token.ok_or(AppError::AuthenticationRequired)?;
+                resolve_session_user(&storage, &token)
+                    .map(|user| (user, None))
+                    .map_err(|e: AppError| -> Rejection { e.into() })
+            },
+        )
+        .untuple_one()
 }
@@ -15,10 +15,10 @@ pub fn backup_routes() -> impl Filter<Extract = (impl Reply,), Error = Rejection
 async fn backup_handler() -> Result<impl Reply, Rejection> {
     // 获取备份目录
     let backup_path = env::var("HOUSE_KEEPER_BACKUP_PATH")
-        .map_err(|e| warp::reject::custom(AppError::BackupError))?;
+        .map_err(|_| warp::reject::custom(AppError::BackupError))?;
     // 获取备份目录
     let storage_base_path = env::var("HOUSE_KEEPER_STORAGE_PATH")
-        .map_err(|e| warp::reject::custom(AppError::BackupError))?;
+        .map_err(|_| warp::reject::custom(AppError::BackupError))?;
 
     let backup_manager = BackupManager::new(&backup_path, &storage_base_path);
 
@@ -54,6 +54,6 @@ async fn backup_handler() -> Result<impl Reply, Rejection> {
 async fn backup_file(backup_manager: &BackupManager, file_path: &str) -> Result<(), Rejection> {
     backup_manager
         .create_backup(file_path)
-        .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+        .map_err(warp::reject::custom)?;
     Ok(())
 }
@@ -0,0 +1,69 @@
+use crate::models::error::AppError;
+use warp::{http::Method, Filter, Rejection};
+
+// 跨站请求防护：非 GET 请求的 Origin / Referer 必须与当前站点一致
+// 使用 Bearer token 的脚本请求不依赖 Cookie，不需要校验
+pub fn csrf_filter() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::any()
+        .and(warp::method())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::header::optional::<String>("origin"))
+        .and(warp::header::optional::<String>("referer"))
+        .and(warp::header::optional::<String>("host"))
+        .and(warp::header::optional::<String>("x-forwarded-host"))
+        .and_then(
+            |method: Method,
+             authorization: Option<String>,
+             origin: Option<String>,
+             referer: Option<String>,
+             host: Option<String>,
+             forwarded_host: Option<String>| async move {
+                if matches!(method, Method::GET | Method::HEAD | Method::OPTIONS)
+                    || authorization.is_some_and(|v| v.starts_with("Bearer "))
+                {
+                    return Ok(());
+                }
+
+                // 浏览器发起的跨站请求一定会携带 Origin 或 Referer，都没有时视为非浏览器请求
+                let source = match origin.or(referer) {
+                    Some(source) => source,
+                    None => return Ok(()),
+                };
+                let source_host = host_of(&source);
+                let allowed = [host, forwarded_host]
+                    .into_iter()
+                    .flatten()
+                    .chain(allowed_origins())
+                    .any(|h| {
+                        source_host
+                            .is_some_and(|s| s.eq_ignore_ascii_case(host_of(&h).unwrap_or(&h)))
+                    });
+
+                if allowed {
+                    Ok(())
+                } else {
+                    log::warn!("Rejected cross-site {} request from {}", method, source);
+                    Err(warp::reject::custom(AppError::CrossSiteRequest))
+                }
+            },
+        )
+        .untuple_one()
+}
+
+// 额外允许的来源，多个用逗号分隔，例如 https://house.example.com
+fn allowed_origins() -> Vec<String> {
+    std::env::var("HOUSE_KEEPER_ALLOWED_ORIGINS")
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 从 URL 中取出 host[:port] 部分，例如 https://example.com:8080/a -> example.com:8080
+fn host_of(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().filter(|h| !h.is_empty())
+}
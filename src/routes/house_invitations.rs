@@ -0,0 +1,408 @@
+use crate::{
+    models::{
+        error::AppError,
+        house::{House, HouseMember, HouseRole},
+        house_invitation::{
+            CreateHouseInvitationForm, HouseInvitation, DEFAULT_HOUSE_INVITATION_TTL_HOURS,
+        },
+        user::User,
+    },
+    routes::{
+        auth::auth_filter,
+        houses::{require_role, require_writable},
+    },
+    storage::file_storage::FileStorage,
+};
+use serde::Serialize;
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+// 被邀请人看到的邀请，附带房屋名称和邀请人
+#[derive(Debug, Serialize)]
+struct PendingInvitationView {
+    #[serde(flatten)]
+    invitation: HouseInvitation,
+    house_name: String,
+    inviter_username: Option<String>,
+}
+
+// 房屋管理者看到的邀请，附带被邀请人的用户名
+#[derive(Debug, Serialize)]
+struct HouseInvitationView {
+    #[serde(flatten)]
+    invitation: HouseInvitation,
+    invitee_username: Option<String>,
+}
+
+pub fn house_invitation_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let create_invitation = warp::path!("houses" / String / "invitations")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(create_invitation_handler);
+
+    let get_house_invitations = warp::path!("houses" / String / "invitations")
+        .and(warp::get())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(get_house_invitations_handler);
+
+    let revoke_invitation = warp::path!("houses" / String / "invitations" / String)
+        .and(warp::delete())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(revoke_invitation_handler);
+
+    let get_my_invitations = warp::path!("invitations")
+        .and(warp::get())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(get_my_invitations_handler);
+
+    let accept_invitation = warp::path!("invitations" / String / "accept")
+        .and(warp::post())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(accept_invitation_handler);
+
+    let decline_invitation = warp::path!("invitations" / String / "decline")
+        .and(warp::post())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(decline_invitation_handler);
+
+    let join_by_link = warp::path!("join" / String)
+        .and(warp::post())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(join_by_link_handler);
+
+    create_invitation
+        .or(get_house_invitations)
+        .or(revoke_invitation)
+        .or(get_my_invitations)
+        .or(accept_invitation)
+        .or(decline_invitation)
+        .or(join_by_link)
+}
+
+async fn create_invitation_handler(
+    house_id: String,
+    form: CreateHouseInvitationForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let invitation = create_invitation(&storage, &house_id, &user, form)?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&invitation),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+// 创建房屋邀请，添加成员的接口也通过它发出邀请
+pub(crate) fn create_invitation(
+    storage: &FileStorage,
+    house_id: &str,
+    user: &User,
+    form: CreateHouseInvitationForm,
+) -> Result<HouseInvitation, Rejection> {
+    let hours = form
+        .expires_in_hours
+        .unwrap_or(DEFAULT_HOUSE_INVITATION_TTL_HOURS);
+    let ttl = chrono::Duration::try_hours(hours)
+        .filter(|_| hours > 0)
+        .ok_or_else(|| warp::reject::custom(AppError::ParameterError))?;
+
+    let houses: Vec<House> = storage
+        .read_json("house.json")
+        .map_err(warp::reject::custom)?;
+    let house = find_house(&houses, house_id)?;
+    let my_role = require_role(house, user, HouseRole::Manager)?;
+    require_writable(house)?;
+
+    // 只有创建人可以邀请 manager，owner 不能通过邀请授予
+    match form.role {
+        HouseRole::Owner => return Err(warp::reject::custom(AppError::ParameterError)),
+        HouseRole::Manager if my_role != HouseRole::Owner => {
+            return Err(warp::reject::custom(AppError::PermissionDenied))
+        }
+        _ => {}
+    }
+
+    // 指定用户时校验用户存在且还不是成员
+    let invitee = match &form.username {
+        Some(username) => {
+            let users: Vec<User> = storage
+                .read_json("user.json")
+                .map_err(warp::reject::custom)?;
+            let invitee = users
+                .iter()
+                .find(|u| u.username == *username)
+                .ok_or_else(|| {
+                    warp::reject::custom(AppError::UnknownUsernames(vec![username.clone()]))
+                })?;
+            if house.role_of(&invitee.id).is_some() {
+                return Err(warp::reject::custom(AppError::AlreadyMember));
+            }
+            Some(invitee.id.clone())
+        }
+        None => None,
+    };
+
+    // 在锁内更新邀请数据，顺便清理已过期的邀请
+    let invitation = HouseInvitation::new(house_id, &user.id, form.role, invitee, ttl);
+    storage
+        .update_json(
+            "house_invitation.json",
+            |invitations: &mut Vec<HouseInvitation>| {
+                invitations.retain(|i| !i.is_expired());
+
+                // 重复邀请同一个用户时替换原来的邀请
+                if invitation.invitee.is_some() {
+                    invitations
+                        .retain(|i| !(i.house_id == house_id && i.invitee == invitation.invitee));
+                }
+                invitations.push(invitation.clone());
+                Ok(())
+            },
+        )
+        .map_err(warp::reject::custom)?;
+
+    Ok(invitation)
+}
+
+async fn get_house_invitations_handler(
+    house_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let houses: Vec<House> = storage
+        .read_json("house.json")
+        .map_err(warp::reject::custom)?;
+    require_role(find_house(&houses, &house_id)?, &user, HouseRole::Manager)?;
+
+    let invitations: Vec<HouseInvitation> = storage
+        .read_json("house_invitation.json")
+        .map_err(warp::reject::custom)?;
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+
+    let views = invitations
+        .into_iter()
+        .filter(|i| i.house_id == house_id && !i.is_expired())
+        .map(|invitation| HouseInvitationView {
+            invitee_username: invitation
+                .invitee
+                .as_ref()
+                .and_then(|id| users.iter().find(|u| u.id == *id))
+                .map(|u| u.username.clone()),
+            invitation,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(warp::reply::json(&views))
+}
+
+async fn revoke_invitation_handler(
+    house_id: String,
+    invitation_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let houses: Vec<House> = storage
+        .read_json("house.json")
+        .map_err(warp::reject::custom)?;
+    require_role(find_house(&houses, &house_id)?, &user, HouseRole::Manager)?;
+
+    storage
+        .update_json(
+            "house_invitation.json",
+            |invitations: &mut Vec<HouseInvitation>| {
+                let index = invitations
+                    .iter()
+                    .position(|i| i.id == invitation_id && i.house_id == house_id)
+                    .ok_or(AppError::NotFound)?;
+                invitations.remove(index);
+                Ok(())
+            },
+        )
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Invitation revoked successfully"
+    })))
+}
+
+async fn get_my_invitations_handler(
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let invitations: Vec<HouseInvitation> = storage
+        .read_json("house_invitation.json")
+        .map_err(warp::reject::custom)?;
+    let houses: Vec<House> = storage
+        .read_json("house.json")
+        .map_err(warp::reject::custom)?;
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+
+    // 只返回发给当前用户、未过期且房屋仍然存在的邀请
+    let views = invitations
+        .into_iter()
+        .filter(|i| i.invitee.as_deref() == Some(user.id.as_str()) && !i.is_expired())
+        .filter_map(|invitation| {
+            let house = houses.iter().find(|h| h.id == invitation.house_id)?;
+            Some(PendingInvitationView {
+                house_name: house.name.clone(),
+                inviter_username: users
+                    .iter()
+                    .find(|u| u.id == invitation.inviter)
+                    .map(|u| u.username.clone()),
+                invitation,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(warp::reply::json(&views))
+}
+
+async fn accept_invitation_handler(
+    invitation_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let invitation = take_invitation(&storage, |i| {
+        i.id == invitation_id && i.invitee.as_deref() == Some(user.id.as_str())
+    })?;
+
+    // 加入失败时归还邀请，例如房屋已归档时保留邀请，取消归档后仍可接受
+    let house_id = match add_member(&storage, &invitation, &user) {
+        Ok(house_id) => house_id,
+        Err(e) => {
+            if let Err(restore_err) = storage.update_json(
+                "house_invitation.json",
+                |invitations: &mut Vec<HouseInvitation>| {
+                    invitations.push(invitation.clone());
+                    Ok(())
+                },
+            ) {
+                log::error!("Failed to restore house invitation: {}", restore_err);
+            }
+            return Err(e);
+        }
+    };
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "house_id": house_id,
+        "message": "Invitation accepted"
+    })))
+}
+
+async fn decline_invitation_handler(
+    invitation_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    take_invitation(&storage, |i| {
+        i.id == invitation_id && i.invitee.as_deref() == Some(user.id.as_str())
+    })?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Invitation declined"
+    })))
+}
+
+async fn join_by_link_handler(
+    code: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    // 分享链接在有效期内可以多次使用，不会被消耗
+    let invitations: Vec<HouseInvitation> = storage
+        .read_json("house_invitation.json")
+        .map_err(warp::reject::custom)?;
+    let invitation = invitations
+        .into_iter()
+        .find(|i| i.code.as_deref() == Some(code.as_str()) && !i.is_expired())
+        .ok_or_else(|| warp::reject::custom(AppError::InvalidInviteCode))?;
+    let house_id = add_member(&storage, &invitation, &user)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "house_id": house_id,
+        "message": "Joined house successfully"
+    })))
+}
+
+// 移除并返回符合条件且未过期的邀请
+fn take_invitation(
+    storage: &FileStorage,
+    matches: impl Fn(&HouseInvitation) -> bool,
+) -> Result<HouseInvitation, Rejection> {
+    storage
+        .update_json(
+            "house_invitation.json",
+            |invitations: &mut Vec<HouseInvitation>| {
+                let index = invitations
+                    .iter()
+                    .position(|i| matches(i) && !i.is_expired())
+                    .ok_or(AppError::NotFound)?;
+                Ok(invitations.remove(index))
+            },
+        )
+        .map_err(warp::reject::custom)
+}
+
+// 按邀请中的角色把用户加入房屋
+fn add_member(
+    storage: &FileStorage,
+    invitation: &HouseInvitation,
+    user: &User,
+) -> Result<String, Rejection> {
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = houses
+                .iter_mut()
+                .find(|h| h.id == invitation.house_id)
+                .ok_or(AppError::HouseNotFound)?;
+            if house.archived {
+                return Err(AppError::HouseArchived);
+            }
+            if house.role_of(&user.id).is_some() {
+                return Err(AppError::AlreadyMember);
+            }
+            house.members.push(HouseMember {
+                user_id: user.id.clone(),
+                username: user.username.clone(),
+                role: invitation.role,
+            });
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    log::info!(
+        "User {} joined house {} as {:?}",
+        user.username,
+        invitation.house_id,
+        invitation.role
+    );
+    Ok(invitation.house_id.clone())
+}
+
+fn find_house<'a>(houses: &'a [House], house_id: &str) -> Result<&'a House, Rejection> {
+    houses
+        .iter()
+        .find(|h| h.id == house_id)
+        .ok_or_else(|| warp::reject::custom(AppError::HouseNotFound))
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
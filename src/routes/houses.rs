@@ -1,17 +1,40 @@
 use crate::{
     models::{
+        api_token::ApiToken,
         error::AppError,
-        house::{CreateHouseForm, House, HouseDetail, HouseMember, SetHouseMembersForm},
+        house::{
+            ArchiveHouseForm, CopyMode, CreateHouseForm, DuplicateHouseForm, House, HouseArea,
+            HouseDetail, HouseListQuery, HouseMember, HouseRole, RenameHouseForm,
+            SetHouseMembersForm,
+        },
+        house_invitation::HouseInvitation,
+        merge::merge_areas,
+        template::HouseTemplate,
+        trash::TrashedHouse,
         user::User,
     },
-    routes::auth::auth_filter,
-    storage::file_storage::FileStorage,
+    routes::{
+        auth::{auth_filter, auth_with_token_filter},
+        images::record_copied_images,
+    },
+    storage::{
+        file_storage::FileStorage,
+        history::{find_snapshot, update_house_detail},
+    },
 };
 use log::info;
+use serde::Serialize;
 use std::convert::Infallible;
-use uuid;
 use warp::{Filter, Rejection};
 
+// 房屋列表中附带当前用户的角色
+#[derive(Debug, Serialize)]
+struct HouseView {
+    #[serde(flatten)]
+    house: House,
+    role: HouseRole,
+}
+
 pub fn houses_routes(
     storage: FileStorage,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
@@ -24,7 +47,8 @@ pub fn houses_routes(
 
     let get_my_houses = warp::path!("houses")
         .and(warp::get())
-        .and(auth_filter(storage.clone()))
+        .and(warp::query::<HouseListQuery>())
+        .and(auth_with_token_filter(storage.clone()))
         .and(with_storage(storage.clone()))
         .and_then(get_my_houses_handler);
 
@@ -34,6 +58,27 @@ pub fn houses_routes(
         .and(with_storage(storage.clone()))
         .and_then(delete_house_handler);
 
+    let duplicate_house = warp::path!("houses" / String / "duplicate")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(duplicate_house_handler);
+
+    let rename_house = warp::path!("houses" / String)
+        .and(warp::patch())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(rename_house_handler);
+
+    let archive_house = warp::path!("houses" / String / "archived")
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(archive_house_handler);
+
     let set_house_members = warp::path!("houses" / String / "members")
         .and(warp::put())
         .and(warp::body::json())
@@ -55,12 +100,25 @@ pub fn houses_routes(
         .and(with_storage(storage.clone()))
         .and_then(update_house_detail_handler);
 
+    // 以 JSON Patch 或 JSON Merge Patch 修改房屋详细数据，按 Content-Type 区分
+    let patch_house_detail = warp::path!("houses" / String / "detail")
+        .and(warp::patch())
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::body::bytes())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(patch_house_detail_handler);
+
     create_house
         .or(get_my_houses)
         .or(delete_house)
+        .or(rename_house)
+        .or(archive_house)
+        .or(duplicate_house)
         .or(set_house_members)
         .or(get_house_detail)
         .or(update_house_detail)
+        .or(patch_house_detail)
 }
 
 async fn create_house_handler(
@@ -68,36 +126,27 @@ async fn create_house_handler(
     user: User,
     storage: FileStorage,
 ) -> Result<impl warp::Reply, Rejection> {
-    // 生成唯一ID
-    let house_id = uuid::Uuid::new_v4().to_string();
-
-    // 读取现有房屋数据并处理错误
-    let mut houses: Vec<House> = storage
-        .read_json("house.json")
-        .map_err(|e| warp::reject::custom(AppError::from(e)))?;
-
-    let new_house: House = House {
-        id: house_id.clone(),
-        name: create_house_form.name.clone(),
-        creator: user.id,
-        members: Vec::new(),
+    // 指定模板时以模板的区域结构作为初始数据
+    let items = match &create_house_form.template_id {
+        Some(template_id) => {
+            let templates: Vec<HouseTemplate> = storage
+                .read_json("template.json")
+                .map_err(warp::reject::custom)?;
+            let template = templates
+                .iter()
+                .find(|t| t.id == *template_id && t.owner == user.id)
+                .ok_or_else(|| warp::reject::custom(AppError::NotFound))?;
+            // 同一个模板可以多次使用，每次都重新生成区域 id
+            template
+                .items
+                .iter()
+                .map(|a| a.duplicate(CopyMode::Full))
+                .collect()
+        }
+        None => Vec::new(),
     };
 
-    // 添加到列表并保存
-    houses.push(new_house);
-    storage
-        .write_json("house.json", &houses)
-        .map_err(|e| warp::reject::custom(AppError::from(e)))?;
-
-    // 创建 house/{house-id}.json 空数据文件
-    let empty_house_detail = HouseDetail {
-        version: uuid::Uuid::new_v4().to_string(),
-        name: create_house_form.name.clone(),
-        items: Vec::new(),
-    };
-    storage
-        .write_json(&format!("house/{}.json", house_id), &empty_house_detail)
-        .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+    let house_id = insert_house(&storage, &create_house_form.name, &user, items)?;
 
     // 返回创建成功的响应
     Ok(warp::reply::with_status(
@@ -109,8 +158,84 @@ async fn create_house_handler(
     ))
 }
 
+async fn duplicate_house_handler(
+    house_id: String,
+    form: DuplicateHouseForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let houses: Vec<House> = storage
+        .read_json("house.json")
+        .map_err(warp::reject::custom)?;
+    let house = houses
+        .iter()
+        .find(|h| h.id == house_id)
+        .ok_or_else(|| warp::reject::custom(AppError::HouseNotFound))?;
+    // 能查看的房屋都可以复制，新房屋属于当前用户；连同图片一起复制需要 editor 及以上角色
+    require_role(house, &user, form.mode.min_role())?;
+
+    let house_detail: HouseDetail = storage
+        .read_json(&format!("house/{}.json", house_id))
+        .map_err(warp::reject::custom)?;
+    let items = house_detail
+        .items
+        .iter()
+        .map(|a| a.duplicate(form.mode))
+        .collect();
+    let name = form
+        .name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("{} 副本", house.name));
+
+    let new_house_id = insert_house(&storage, &name, &user, items)?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "id": new_house_id,
+            "message": "House duplicated successfully"
+        })),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+// 创建房屋：先写入 house/{house-id}.json，再加入 house.json，返回新房屋的 ID
+fn insert_house(
+    storage: &FileStorage,
+    name: &str,
+    user: &User,
+    items: Vec<HouseArea>,
+) -> Result<String, Rejection> {
+    // 生成唯一ID
+    let new_house = House::new(name, &user.id);
+    let house_id = new_house.id.clone();
+
+    let image_names = items.iter().flat_map(|a| a.image_names()).collect();
+    let mut house_detail = HouseDetail::new();
+    house_detail.name = name.to_string();
+    house_detail.items = items;
+    storage
+        .write_json(&format!("house/{}.json", house_id), &house_detail)
+        .map_err(warp::reject::custom)?;
+
+    // 在新房屋可见之前校验复制过来的图片，避免借新房屋的引用获得查看权限
+    record_copied_images(storage, user, &house_id, image_names)?;
+
+    // 添加到列表并保存
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            houses.push(new_house);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(house_id)
+}
+
 async fn get_my_houses_handler(
+    query: HouseListQuery,
     user: User,
+    api_token: Option<ApiToken>,
     storage: FileStorage,
 ) -> Result<impl warp::Reply, Rejection> {
     // 记录进入方法时的传参
@@ -119,13 +244,22 @@ async fn get_my_houses_handler(
     // 读取房屋数据
     let houses: Vec<House> = storage
         .read_json("house.json")
-        .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+        .map_err(warp::reject::custom)?;
 
     // 过滤属于当前用户的房屋
     let my_houses = houses
         .into_iter()
+        // 限定房屋的访问令牌只能看到授权的房屋
         .filter(|house| {
-            house.creator == user.id || house.members.iter().any(|member| member.user_id == user.id)
+            api_token
+                .as_ref()
+                .is_none_or(|t| t.can_access_house(&house.id))
+        })
+        .filter(|house| query.include_archived || !house.archived)
+        .filter_map(|house| {
+            house
+                .role_of(&user.id)
+                .map(|role| HouseView { house, role })
         })
         .collect::<Vec<_>>();
 
@@ -141,100 +275,252 @@ async fn delete_house_handler(
     storage: FileStorage,
 ) -> Result<impl warp::Reply, Rejection> {
     // 读取房屋数据
-    let mut houses: Vec<House> = storage
+    let houses: Vec<House> = storage
         .read_json("house.json")
-        .map_err(|e| warp::reject::custom(AppError::from(e)))?;
-
-    // 查找要删除的房屋
-    if let Some(index) = houses.iter().position(|house| house.id == house_id) {
-        let house = &houses[index];
-        // 校验房屋创建人是否为当前用户
-        if house.creator != user.id {
-            return Err(warp::reject::custom(AppError::PermissionDenied));
-        }
+        .map_err(warp::reject::custom)?;
 
-        // 移除房屋记录
-        houses.remove(index);
-        storage
-            .write_json("house.json", &houses)
-            .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+    // 查找要删除的房屋，只有创建人可以删除房屋
+    let mut house = houses
+        .into_iter()
+        .find(|house| house.id == house_id)
+        .ok_or_else(|| warp::reject::custom(AppError::HouseNotFound))?;
+    require_role(&house, &user, HouseRole::Owner)?;
+
+    // 先移入回收站再从房屋列表中移除，中途失败时房屋不会丢失，house/{house-id}.json 保留到彻底清除为止
+    house.pending_transfer = None;
+    let trashed = TrashedHouse::new(house, &user.id);
+    let deleted_at = trashed.deleted_at;
+    storage
+        .update_json("trash.json", |trash: &mut Vec<TrashedHouse>| {
+            trash.push(trashed);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
 
-        // 删除 house/{house-id}.json 文件
-        if let Err(e) = storage.delete_file(&format!("house/{}.json", house_id)) {
-            return Err(warp::reject::custom(AppError::from(e)));
+    let removed = storage.update_json("house.json", |houses: &mut Vec<House>| {
+        let index = houses
+            .iter()
+            .position(|h| h.id == house_id)
+            .ok_or(AppError::HouseNotFound)?;
+        check_role(&houses[index], &user, HouseRole::Owner)?;
+        Ok(houses.remove(index))
+    });
+    let trash_result = match removed {
+        // 回收站中保存移除时的最新数据，例如期间修改过的成员
+        Ok(mut house) => {
+            house.pending_transfer = None;
+            storage.update_json("trash.json", |trash: &mut Vec<TrashedHouse>| {
+                if let Some(t) = trash
+                    .iter_mut()
+                    .find(|t| t.house.id == house_id && t.deleted_at == deleted_at)
+                {
+                    t.house = house;
+                }
+                Ok(())
+            })
         }
-
-        // 返回删除成功的响应
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
-                "message": "House deleted successfully"
-            })),
-            warp::http::StatusCode::OK,
-        ));
+        // 房屋已被删除或创建人已变更，撤回刚加入回收站的记录
+        Err(e) => {
+            storage
+                .update_json("trash.json", |trash: &mut Vec<TrashedHouse>| {
+                    trash.retain(|t| !(t.house.id == house_id && t.deleted_at == deleted_at));
+                    Ok(())
+                })
+                .map_err(warp::reject::custom)?;
+            return Err(warp::reject::custom(e));
+        }
+    };
+    if let Err(e) = trash_result {
+        log::warn!("Failed to refresh trashed house {}: {}", house_id, e);
     }
 
-    // 若未找到房屋，返回错误
-    Err(warp::reject::custom(AppError::HouseNotFound))
+    // 清理该房屋的邀请
+    storage
+        .update_json(
+            "house_invitation.json",
+            |invitations: &mut Vec<HouseInvitation>| {
+                invitations.retain(|i| i.house_id != house_id);
+                Ok(())
+            },
+        )
+        .map_err(warp::reject::custom)?;
+
+    // 返回删除成功的响应
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "message": "House moved to trash"
+        })),
+        warp::http::StatusCode::OK,
+    ))
 }
 
-async fn set_house_members_handler(
+async fn rename_house_handler(
     house_id: String,
-    form: SetHouseMembersForm,
+    form: RenameHouseForm,
     user: User,
     storage: FileStorage,
 ) -> Result<impl warp::Reply, Rejection> {
+    let name = form.name.trim().to_string();
+    if name.is_empty() {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
     // 读取房屋数据
-    let mut houses: Vec<House> = storage
+    let houses: Vec<House> = storage
         .read_json("house.json")
-        .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+        .map_err(warp::reject::custom)?;
 
-    // 查找要修改的房屋
-    if let Some(house) = houses.iter_mut().find(|h| h.id == house_id) {
-        // 校验房屋创建人是否为当前用户
-        if house.creator != user.id {
-            return Err(warp::reject::custom(AppError::PermissionDenied));
-        }
+    let house = houses
+        .iter()
+        .find(|h| h.id == house_id)
+        .ok_or_else(|| warp::reject::custom(AppError::HouseNotFound))?;
+    require_role(house, &user, HouseRole::Manager)?;
+    require_writable(house)?;
 
-        // 读取用户数据
-        let users: Vec<User> = storage
-            .read_json("user.json")
-            .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+    // 先改详细数据再改房屋列表，中途失败时启动时的一致性检查会以 house.json 为准修复
+    // 名称不属于房屋内容，不更新 version，避免其他人正在编辑的内容产生冲突
+    storage
+        .update_json(
+            &format!("house/{}.json", house_id),
+            |house_detail: &mut HouseDetail| {
+                house_detail.name = name.clone();
+                Ok(())
+            },
+        )
+        .map_err(warp::reject::custom)?;
 
-        // 根据用户名列表找到相应的用户
-        let members = form
-            .usernames
-            .iter()
-            .filter_map(|username| {
-                users.iter().find(|u| u.username == *username).map(|u| {
-                    // Create a HouseMember instance here.
-                    // Assume HouseMember has fields id and username.
-                    HouseMember {
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = houses
+                .iter_mut()
+                .find(|h| h.id == house_id)
+                .ok_or(AppError::HouseNotFound)?;
+            check_role(house, &user, HouseRole::Manager)?;
+            house.name = name.clone();
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "id": house_id,
+        "name": name,
+        "message": "House renamed successfully"
+    })))
+}
+
+async fn archive_house_handler(
+    house_id: String,
+    form: ArchiveHouseForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = houses
+                .iter_mut()
+                .find(|h| h.id == house_id)
+                .ok_or(AppError::HouseNotFound)?;
+            // 只有创建人可以归档或取消归档
+            if house.role_of(&user.id) != Some(HouseRole::Owner) {
+                return Err(AppError::PermissionDenied);
+            }
+            house.archived = form.archived;
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+    info!(
+        "User {} set archived={} for house {}",
+        user.username, form.archived, house_id
+    );
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "id": house_id,
+        "archived": form.archived,
+        "message": "House archive state updated successfully"
+    })))
+}
+
+async fn set_house_members_handler(
+    house_id: String,
+    form: SetHouseMembersForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    // 成员只能被分配 viewer、editor、manager 角色
+    if form.roles.values().any(|r| *r == HouseRole::Owner) {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
+    // 读取用户数据
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+
+    // 在锁内修改房屋数据，避免覆盖同时进行的单个成员修改
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            // 查找要修改的房屋
+            let house = houses
+                .iter_mut()
+                .find(|h| h.id == house_id)
+                .ok_or(AppError::HouseNotFound)?;
+
+            // 管理员及以上角色才能管理成员
+            let my_role = check_role(house, &user, HouseRole::Manager)?;
+            if house.archived {
+                return Err(AppError::HouseArchived);
+            }
+
+            // 根据用户名列表找到相应的用户，存在未知用户名时整体失败
+            let mut members = Vec::new();
+            let mut unknown = Vec::new();
+            for username in &form.usernames {
+                match users.iter().find(|u| u.username == *username) {
+                    Some(u) => members.push(HouseMember {
                         user_id: u.id.clone(),
                         username: u.username.clone(),
-                    }
-                })
-            })
-            .collect::<Vec<HouseMember>>();
-
-        // 修改房屋的 members 字段
-        house.members = members;
-
-        // 保存修改后的房屋数据
-        storage
-            .write_json("house.json", &houses)
-            .map_err(|e| warp::reject::custom(AppError::from(e)))?;
-
-        // 返回修改成功的响应
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
-                "message": "House members updated successfully"
-            })),
-            warp::http::StatusCode::OK,
-        ));
-    }
+                        role: form
+                            .roles
+                            .get(username)
+                            .copied()
+                            .or_else(|| house.role_of(&u.id).filter(|r| *r != HouseRole::Owner))
+                            .unwrap_or_default(),
+                    }),
+                    None => unknown.push(username.clone()),
+                }
+            }
+            if !unknown.is_empty() {
+                return Err(AppError::UnknownUsernames(unknown));
+            }
+
+            // 新成员必须通过邀请加入，这里只能调整已有成员的角色或移除成员
+            let uninvited = members
+                .iter()
+                .filter(|m| house.role_of(&m.user_id).is_none())
+                .map(|m| m.username.clone())
+                .collect::<Vec<_>>();
+            if !uninvited.is_empty() {
+                return Err(AppError::InvitationRequired(uninvited));
+            }
+
+            // 只有创建人可以任免 manager
+            if my_role != HouseRole::Owner && managers(&house.members) != managers(&members) {
+                return Err(AppError::PermissionDenied);
+            }
+
+            // 修改房屋的 members 字段
+            house.members = members;
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
 
-    // 若未找到房屋，返回错误
-    Err(warp::reject::custom(AppError::HouseNotFound))
+    // 返回修改成功的响应
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "message": "House members updated successfully"
+        })),
+        warp::http::StatusCode::OK,
+    ))
 }
 
 async fn get_house_detail_handler(
@@ -245,20 +531,17 @@ async fn get_house_detail_handler(
     // 读取房屋数据
     let houses: Vec<House> = storage
         .read_json("house.json")
-        .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+        .map_err(warp::reject::custom)?;
 
     // 查找要查询的房屋
     if let Some(house) = houses.iter().find(|h| h.id == house_id) {
-        // 校验当前用户是否为房屋的创建人或成员
-        if house.creator != user.id && !house.members.iter().any(|member| member.user_id == user.id)
-        {
-            return Err(warp::reject::custom(AppError::PermissionDenied));
-        }
+        // 所有成员都可以查看
+        require_role(house, &user, HouseRole::Viewer)?;
 
         // 读取房屋详细数据
         let house_detail: HouseDetail = storage
             .read_json(&format!("house/{}.json", house_id))
-            .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+            .map_err(warp::reject::custom)?;
 
         // 返回房屋详细数据
         return Ok(warp::reply::json(&house_detail));
@@ -270,54 +553,254 @@ async fn get_house_detail_handler(
 
 async fn update_house_detail_handler(
     house_id: String,
-    new_house_detail: HouseDetail,
+    mut new_house_detail: HouseDetail,
     user: User,
     storage: FileStorage,
 ) -> Result<impl warp::Reply, Rejection> {
     // 读取房屋数据
     let houses: Vec<House> = storage
         .read_json("house.json")
-        .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+        .map_err(warp::reject::custom)?;
 
     // 查找要修改的房屋
     if let Some(house) = houses.iter().find(|h| h.id == house_id) {
-        // 校验当前用户是否为房屋的创建人或成员
-        if house.creator != user.id && !house.members.iter().any(|member| member.user_id == user.id)
-        {
-            return Err(warp::reject::custom(AppError::PermissionDenied));
+        // editor 及以上角色才能修改
+        require_role(house, &user, HouseRole::Editor)?;
+        require_writable(house)?;
+
+        // 提交的数据必须是合法的房屋详细数据（区域 ID 非空且不重复）
+        new_house_detail
+            .validate()
+            .map_err(|e| warp::reject::custom(AppError::ParseError(e)))?;
+
+        // 读取、校验并写入房屋详细数据，与按区域修改的接口互斥
+        let result = update_house_detail(&storage, &house_id, |current_house_detail| {
+            // 版本号不匹配时，以客户端开始编辑时的历史快照为基础自动合并
+            let merged = current_house_detail.version != new_house_detail.version;
+            if merged {
+                let base = find_snapshot(&storage, &house_id, &new_house_detail.version)?
+                    .ok_or(AppError::VersionMismatch)?;
+                new_house_detail.items = merge_areas(
+                    &base.items,
+                    &current_house_detail.items,
+                    &new_house_detail.items,
+                )
+                .map_err(AppError::MergeConflict)?;
+                // 合并结果同样需要校验
+                new_house_detail.validate().map_err(AppError::ParseError)?;
+            }
+
+            let previous = std::mem::replace(current_house_detail, new_house_detail);
+            // 有变化的区域生成新的版本号，供按区域修改的接口检测冲突
+            current_house_detail.stamp_versions(&previous);
+            // 名称只能通过 PATCH /houses/{id} 修改，这里以 house.json 为准
+            current_house_detail.name = house.name.clone();
+            // 生成新的版本号
+            Ok((current_house_detail.touch(), merged))
+        });
+
+        return match result {
+            // 返回新的版本号，merged 为 true 时客户端需要重新获取合并后的数据
+            Ok((new_version, merged)) => Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "version": new_version,
+                    "merged": merged
+                })),
+                warp::http::StatusCode::OK,
+            )),
+            // 合并冲突时返回冲突列表，不保存任何修改
+            Err(AppError::MergeConflict(conflicts)) => {
+                let current_version = storage
+                    .read_json::<HouseDetail>(&format!("house/{}.json", house_id))
+                    .map(|d| d.version)
+                    .map_err(warp::reject::custom)?;
+                Ok(warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({
+                        "error": AppError::MergeConflict(conflicts.clone()).to_string(),
+                        "version": current_version,
+                        "conflicts": conflicts
+                    })),
+                    warp::http::StatusCode::CONFLICT,
+                ))
+            }
+            Err(e) => Err(warp::reject::custom(e)),
+        };
+    }
+
+    // 若未找到房屋，返回错误
+    Err(warp::reject::custom(AppError::HouseNotFound))
+}
+
+async fn patch_house_detail_handler(
+    house_id: String,
+    content_type: Option<String>,
+    body: bytes::Bytes,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let patch = DetailPatch::parse(content_type.as_deref().unwrap_or_default(), &body)
+        .map_err(warp::reject::custom)?;
+
+    let houses: Vec<House> = storage
+        .read_json("house.json")
+        .map_err(warp::reject::custom)?;
+    let house = houses
+        .iter()
+        .find(|h| h.id == house_id)
+        .ok_or_else(|| warp::reject::custom(AppError::HouseNotFound))?;
+    require_role(house, &user, HouseRole::Editor)?;
+    require_writable(house)?;
+
+    let new_version = update_house_detail(&storage, &house_id, |house_detail| {
+        // 补丁没有改变任何数据时不生成新的版本号
+        let Some(patched) = patch.patch_detail(house_detail)? else {
+            return Ok(house_detail.version.clone());
+        };
+
+        // 有变化的区域生成新的版本号，供按区域修改的接口检测冲突
+        let previous = std::mem::replace(house_detail, patched);
+        house_detail.stamp_versions(&previous);
+        house_detail.name = house.name.clone();
+        Ok(house_detail.touch())
+    })
+    .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "version": new_version
+    })))
+}
+
+// 房屋详细数据的补丁，test 操作可以用来校验 version 等字段，实现乐观锁
+enum DetailPatch {
+    // RFC 6902 JSON Patch
+    Json(json_patch::Patch),
+    // RFC 7386 JSON Merge Patch
+    Merge(serde_json::Value),
+}
+
+impl DetailPatch {
+    fn parse(content_type: &str, body: &[u8]) -> Result<Self, AppError> {
+        // 忽略 charset 等参数
+        let media_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match media_type.as_str() {
+            "application/json-patch+json" => serde_json::from_slice(body)
+                .map(DetailPatch::Json)
+                .map_err(|e| AppError::InvalidPatch(e.to_string())),
+            "application/merge-patch+json" => serde_json::from_slice(body)
+                .map(DetailPatch::Merge)
+                .map_err(|e| AppError::InvalidPatch(e.to_string())),
+            _ => Err(AppError::UnsupportedMediaType(media_type)),
         }
+    }
 
-        // 读取房屋详细数据
-        let mut current_house_detail: HouseDetail = storage
-            .read_json(&format!("house/{}.json", house_id))
-            .map_err(|e| warp::reject::custom(AppError::from(e)))?;
+    // 对房屋详细数据打补丁，数据没有变化时返回 None
+    fn patch_detail(&self, detail: &HouseDetail) -> Result<Option<HouseDetail>, AppError> {
+        let original =
+            serde_json::to_value(detail).map_err(|e| AppError::ParseError(e.to_string()))?;
+        let mut doc = original.clone();
+        self.apply(&mut doc)?;
+        if doc == original {
+            return Ok(None);
+        }
 
-        // 检查版本号是否匹配
-        if current_house_detail.version != new_house_detail.version {
-            return Err(warp::reject::custom(AppError::VersionMismatch));
+        // 名称只能通过 PATCH /houses/{id} 修改，版本号由服务端生成
+        for field in ["name", "version"] {
+            if doc.get(field) != original.get(field) {
+                return Err(AppError::InvalidPatch(format!(
+                    "patch must not change `{}`",
+                    field
+                )));
+            }
         }
 
-        // 生成新的版本号
-        let new_version = uuid::Uuid::new_v4().to_string();
-        current_house_detail.version = new_version.clone();
-        current_house_detail.items = new_house_detail.items;
-
-        // 写入新的房屋详细数据
-        storage
-            .write_json(&format!("house/{}.json", house_id), &current_house_detail)
-            .map_err(|e| warp::reject::custom(AppError::from(e)))?;
-
-        // 返回新的版本号
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({
-                "version": new_version
-            })),
-            warp::http::StatusCode::OK,
-        ));
+        // 打补丁后的结果必须仍然是合法的房屋详细数据
+        let patched: HouseDetail = serde_json::from_value(doc).map_err(|e| {
+            AppError::InvalidPatch(format!("patched house detail is invalid: {}", e))
+        })?;
+        patched.validate().map_err(|e| {
+            AppError::InvalidPatch(format!("patched house detail is invalid: {}", e))
+        })?;
+        Ok(Some(patched))
     }
 
-    // 若未找到房屋，返回错误
-    Err(warp::reject::custom(AppError::HouseNotFound))
+    fn apply(&self, doc: &mut serde_json::Value) -> Result<(), AppError> {
+        match self {
+            DetailPatch::Json(patch) => json_patch::patch(doc, patch).map_err(|e| {
+                // 指明失败的是第几个操作，以及操作类型和路径
+                let op = patch.0.get(e.operation).map_or("unknown", operation_name);
+                let msg = format!(
+                    "operation {} ({} {}) failed: {}",
+                    e.operation, op, e.path, e.kind
+                );
+                match e.kind {
+                    json_patch::PatchErrorKind::TestFailed => AppError::PatchTestFailed(msg),
+                    _ => AppError::InvalidPatch(msg),
+                }
+            }),
+            DetailPatch::Merge(patch) => {
+                json_patch::merge(doc, patch);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn operation_name(op: &json_patch::PatchOperation) -> &'static str {
+    match op {
+        json_patch::PatchOperation::Add(_) => "add",
+        json_patch::PatchOperation::Remove(_) => "remove",
+        json_patch::PatchOperation::Replace(_) => "replace",
+        json_patch::PatchOperation::Move(_) => "move",
+        json_patch::PatchOperation::Copy(_) => "copy",
+        json_patch::PatchOperation::Test(_) => "test",
+    }
+}
+
+// 校验当前用户在房屋中的角色不低于 min，返回实际角色
+pub(crate) fn require_role(
+    house: &House,
+    user: &User,
+    min: HouseRole,
+) -> Result<HouseRole, Rejection> {
+    house
+        .role_of(&user.id)
+        .filter(|role| *role >= min)
+        .ok_or_else(|| warp::reject::custom(AppError::PermissionDenied))
+}
+
+// 与 require_role 相同，在 update_json 的闭包中使用 AppError
+pub(crate) fn check_role(
+    house: &House,
+    user: &User,
+    min: HouseRole,
+) -> Result<HouseRole, AppError> {
+    house
+        .role_of(&user.id)
+        .filter(|role| *role >= min)
+        .ok_or(AppError::PermissionDenied)
+}
+
+// 已归档的房屋是只读的，拒绝所有修改
+pub(crate) fn require_writable(house: &House) -> Result<(), Rejection> {
+    if house.archived {
+        return Err(warp::reject::custom(AppError::HouseArchived));
+    }
+    Ok(())
+}
+
+fn managers(members: &[HouseMember]) -> Vec<&str> {
+    let mut ids = members
+        .iter()
+        .filter(|m| m.role == HouseRole::Manager)
+        .map(|m| m.user_id.as_str())
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids
 }
 
 fn with_storage(
@@ -325,3 +808,113 @@ fn with_storage(
 ) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
     warp::any().map(move || storage.clone())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn json_patch(ops: serde_json::Value) -> DetailPatch {
+        DetailPatch::parse("application/json-patch+json", ops.to_string().as_bytes()).unwrap()
+    }
+
+    fn detail() -> serde_json::Value {
+        json!({ "version": "v1", "name": "Home", "items": [] })
+    }
+
+    #[test]
+    fn parse_ignores_media_type_parameters() {
+        let patch = DetailPatch::parse("Application/Merge-Patch+JSON; charset=utf-8", b"{}");
+        assert!(matches!(patch, Ok(DetailPatch::Merge(_))));
+    }
+
+    #[test]
+    fn parse_rejects_unsupported_media_type() {
+        let err = DetailPatch::parse("application/json", b"{}").err().unwrap();
+        assert!(matches!(err, AppError::UnsupportedMediaType(t) if t == "application/json"));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_patch() {
+        let err = DetailPatch::parse("application/json-patch+json", b"{}")
+            .err()
+            .unwrap();
+        assert!(matches!(err, AppError::InvalidPatch(_)));
+    }
+
+    #[test]
+    fn failed_operation_is_named_by_index_type_and_path() {
+        let patch = json_patch(json!([
+            { "op": "replace", "path": "/name", "value": "Flat" },
+            { "op": "remove", "path": "/items/3" }
+        ]));
+        let mut doc = detail();
+
+        match patch.apply(&mut doc) {
+            Err(AppError::InvalidPatch(msg)) => {
+                assert!(
+                    msg.starts_with("operation 1 (remove /items/3) failed"),
+                    "{}",
+                    msg
+                )
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn failed_test_operation_is_reported_as_test_failure() {
+        let patch = json_patch(json!([
+            { "op": "test", "path": "/version", "value": "v0" },
+            { "op": "replace", "path": "/name", "value": "Flat" }
+        ]));
+        let mut doc = detail();
+
+        match patch.apply(&mut doc) {
+            Err(AppError::PatchTestFailed(msg)) => {
+                assert!(
+                    msg.starts_with("operation 0 (test /version) failed"),
+                    "{}",
+                    msg
+                )
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn patch_changing_name_or_version_is_rejected() {
+        let detail: HouseDetail = serde_json::from_value(detail()).unwrap();
+        for (field, value) in [("name", json!("Flat")), ("version", json!("v2"))] {
+            let patch = json_patch(json!([
+                { "op": "replace", "path": format!("/{}", field), "value": value }
+            ]));
+
+            match patch.patch_detail(&detail) {
+                Err(AppError::InvalidPatch(msg)) => assert!(msg.contains(field), "{}", msg),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn patch_without_changes_returns_none() {
+        let detail: HouseDetail = serde_json::from_value(detail()).unwrap();
+        let patch = json_patch(json!([
+            { "op": "test", "path": "/version", "value": "v1" }
+        ]));
+
+        assert!(patch.patch_detail(&detail).unwrap().is_none());
+    }
+
+    #[test]
+    fn merge_patch_is_applied() {
+        let patch =
+            DetailPatch::parse("application/merge-patch+json", br#"{"name": "Flat"}"#).unwrap();
+        let mut doc = detail();
+
+        patch.apply(&mut doc).unwrap();
+        assert_eq!(doc["name"], json!("Flat"));
+        assert_eq!(doc["version"], json!("v1"));
+    }
+}
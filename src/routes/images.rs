@@ -1,7 +1,15 @@
 use crate::{
-    models::error::AppError,
-    models::user::User,
-    routes::auth::auth_filter,
+    models::{
+        api_token::ApiToken,
+        error::AppError,
+        house::{House, HouseDetail, HouseRole},
+        image::{ImageRecord, UploadImageQuery},
+        user::User,
+    },
+    routes::{
+        auth::auth_with_token_filter,
+        houses::{require_role, require_writable},
+    },
     storage::file_storage::FileStorage,
 };
 use bytes::Buf;
@@ -22,27 +30,54 @@ pub fn image_routes(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     // 图片上传路由，支持 multipart 文件上传
     let upload_image = warp::path!("images")
-       .and(warp::post())
-       .and(warp::multipart::form().max_length(5 * 1024 * 1024))
-       .and(auth_filter(storage.clone()))
-       .and(with_storage(storage.clone()))
-       .and_then(upload_image_handler);
+        .and(warp::post())
+        .and(warp::query::<UploadImageQuery>())
+        .and(warp::multipart::form().max_length(5 * 1024 * 1024))
+        .and(auth_with_token_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(upload_image_handler);
 
     // 图片下载路由
     let download_image = warp::path!("images" / String)
-       .and(warp::get())
-       .and(auth_filter(storage.clone()))
-       .and(with_storage(storage.clone()))
-       .and_then(download_image_handler);
+        .and(warp::get())
+        .and(auth_with_token_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(download_image_handler);
 
     upload_image.or(download_image)
 }
 
 async fn upload_image_handler(
+    query: UploadImageQuery,
     mut form: FormData,
-    user: User, // 校验登录用户
+    user: User,
+    api_token: Option<ApiToken>,
     storage: FileStorage,
 ) -> Result<impl warp::Reply, Rejection> {
+    // 限定房屋的访问令牌只能上传到授权的房屋
+    if let Some(token) = &api_token {
+        let allowed = match &query.house_id {
+            Some(house_id) => token.can_access_house(house_id),
+            None => token.house_ids.is_none(),
+        };
+        if !allowed {
+            return Err(warp::reject::custom(AppError::PermissionDenied));
+        }
+    }
+
+    // 上传到房屋的图片要求 editor 及以上角色
+    if let Some(house_id) = &query.house_id {
+        let houses: Vec<House> = storage
+            .read_json("house.json")
+            .map_err(warp::reject::custom)?;
+        let house = houses
+            .iter()
+            .find(|h| h.id == *house_id)
+            .ok_or_else(|| warp::reject::custom(AppError::HouseNotFound))?;
+        require_role(house, &user, HouseRole::Editor)?;
+        require_writable(house)?;
+    }
+
     // 从表单数据中获取文件
     if let Some(field) = form.next().await {
         let field =
@@ -75,7 +110,16 @@ async fn upload_image_handler(
             // 写入文件
             storage
                 .write_file(&path, &bytes)
-                .map_err(|e| warp::reject::custom(e))?;
+                .map_err(warp::reject::custom)?;
+
+            // 记录图片归属
+            let record = ImageRecord::new(&random_filename, query.house_id, &user.id);
+            storage
+                .update_json("image.json", |records: &mut Vec<ImageRecord>| {
+                    records.push(record);
+                    Ok(())
+                })
+                .map_err(warp::reject::custom)?;
 
             // 返回文件名给前端
             return Ok(warp::reply::json(&serde_json::json!({
@@ -89,9 +133,14 @@ async fn upload_image_handler(
 
 async fn download_image_handler(
     filename: String,
-    user: User, // 校验登录用户
+    user: User,
+    api_token: Option<ApiToken>,
     storage: FileStorage,
 ) -> Result<impl warp::Reply, Rejection> {
+    if !can_view_image(&storage, &user, api_token.as_ref(), &filename)? {
+        return Err(warp::reject::custom(AppError::PermissionDenied));
+    }
+
     let path = format!("images/{}", filename);
     let file_content = storage
        .read_file(&path)
@@ -109,6 +158,84 @@ async fn download_image_handler(
     Ok(response)
 }
 
+// 房屋图片需要至少 viewer 角色，个人图片所有登录用户可见
+fn can_view_image(
+    storage: &FileStorage,
+    user: &User,
+    api_token: Option<&ApiToken>,
+    filename: &str,
+) -> Result<bool, Rejection> {
+    let records: Vec<ImageRecord> = storage
+        .read_json("image.json")
+        .map_err(warp::reject::custom)?;
+    // 当前用户可见的房屋，访问令牌还需要被授权
+    let houses: Vec<House> = storage
+        .read_json::<Vec<House>>("house.json")
+        .map_err(warp::reject::custom)?
+        .into_iter()
+        .filter(|h| h.role_of(&user.id).is_some())
+        .filter(|h| api_token.is_none_or(|t| t.can_access_house(&h.id)))
+        .collect();
+
+    // 有归属记录的图片只按记录校验，复制到其他房屋时会为新房屋添加记录
+    let mut records = records.iter().filter(|r| r.file_name == filename).peekable();
+    if records.peek().is_some() {
+        return Ok(records.any(|r| match &r.house_id {
+            Some(house_id) => houses.iter().any(|h| h.id == *house_id),
+            None => true,
+        }));
+    }
+
+    // 没有归属记录的旧图片：头像，或者被当前用户可见的房屋引用
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+    if users.iter().any(|u| u.avatar.as_deref() == Some(filename)) {
+        return Ok(true);
+    }
+    Ok(houses.iter().any(|h| {
+        storage
+            .read_json::<HouseDetail>(&format!("house/{}.json", h.id))
+            .is_ok_and(|detail| detail.references_image(filename))
+    }))
+}
+
+// 复制房屋或以模板创建房屋时，为新房屋记录图片归属，只记录当前用户能查看的图片
+// 没有归属记录的旧图片不添加记录，以免原房屋的成员失去查看权限
+pub(crate) fn record_copied_images(
+    storage: &FileStorage,
+    user: &User,
+    house_id: &str,
+    names: Vec<String>,
+) -> Result<(), Rejection> {
+    let records: Vec<ImageRecord> = storage
+        .read_json("image.json")
+        .map_err(warp::reject::custom)?;
+    let mut copied = Vec::new();
+    for name in names {
+        if !copied.contains(&name)
+            && records.iter().any(|r| r.file_name == name)
+            && can_view_image(storage, user, None, &name)?
+        {
+            copied.push(name);
+        }
+    }
+    if copied.is_empty() {
+        return Ok(());
+    }
+
+    storage
+        .update_json("image.json", |records: &mut Vec<ImageRecord>| {
+            records.extend(
+                copied
+                    .iter()
+                    .map(|name| ImageRecord::new(name, Some(house_id.to_string()), &user.id)),
+            );
+            Ok(())
+        })
+        .map_err(warp::reject::custom)
+}
+
 fn get_content_type(filename: &str) -> &str {
     let ext = Path::new(filename).extension().and_then(OsStr::to_str).unwrap_or("");
     match ext {
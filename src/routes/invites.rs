@@ -0,0 +1,112 @@
+use crate::{
+    models::{
+        error::AppError,
+        invite::{CreateInviteForm, InviteCode, DEFAULT_INVITE_TTL_HOURS},
+        user::User,
+    },
+    routes::auth::auth_filter,
+    storage::file_storage::FileStorage,
+};
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+pub fn invite_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let create_invite = warp::path!("invites")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(create_invite_handler);
+
+    let get_my_invites = warp::path!("invites")
+        .and(warp::get())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(get_my_invites_handler);
+
+    let revoke_invite = warp::path!("invites" / String)
+        .and(warp::delete())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(revoke_invite_handler);
+
+    create_invite.or(get_my_invites).or(revoke_invite)
+}
+
+async fn create_invite_handler(
+    form: CreateInviteForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let hours = form.expires_in_hours.unwrap_or(DEFAULT_INVITE_TTL_HOURS);
+    let ttl = chrono::Duration::try_hours(hours)
+        .filter(|_| hours > 0)
+        .ok_or_else(|| warp::reject::custom(AppError::ParameterError))?;
+
+    // 在锁内添加邀请码，顺便清理已过期的邀请码
+    let invite = InviteCode::new(&user.id, ttl);
+    storage
+        .update_json("invite.json", |invites: &mut Vec<InviteCode>| {
+            invites.retain(|invite| !invite.is_expired());
+            invites.push(invite.clone());
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&invite),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+async fn get_my_invites_handler(
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let invites: Vec<InviteCode> = storage
+        .read_json("invite.json")
+        .map_err(warp::reject::custom)?;
+
+    // 只返回当前用户创建且未过期的邀请码
+    let my_invites = invites
+        .into_iter()
+        .filter(|invite| invite.creator == user.id && !invite.is_expired())
+        .collect::<Vec<_>>();
+
+    Ok(warp::reply::json(&my_invites))
+}
+
+async fn revoke_invite_handler(
+    code: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("invite.json", |invites: &mut Vec<InviteCode>| {
+            let index = invites
+                .iter()
+                .position(|invite| invite.code == code)
+                .ok_or(AppError::NotFound)?;
+
+            // 只有邀请码的创建人才能撤销
+            if invites[index].creator != user.id {
+                return Err(AppError::PermissionDenied);
+            }
+
+            invites.remove(index);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Invite code revoked successfully"
+    })))
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
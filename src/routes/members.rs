@@ -0,0 +1,301 @@
+use crate::{
+    models::{
+        error::AppError,
+        house::{
+            AddHouseMemberForm, House, HouseRole, OwnershipTransfer, SetMemberRoleForm,
+            TransferOwnershipForm,
+        },
+        house_invitation::CreateHouseInvitationForm,
+        user::User,
+    },
+    routes::{auth::auth_filter, house_invitations::create_invitation, houses::check_role},
+    storage::file_storage::FileStorage,
+};
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+// 单个成员的增删改，每次只修改一个成员，并发操作不会互相覆盖
+pub fn member_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let add_member = warp::path!("houses" / String / "members")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(add_member_handler);
+
+    let set_member_role = warp::path!("houses" / String / "members" / String)
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(set_member_role_handler);
+
+    let remove_member = warp::path!("houses" / String / "members" / String)
+        .and(warp::delete())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(remove_member_handler);
+
+    let leave_house = warp::path!("houses" / String / "leave")
+        .and(warp::post())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(leave_house_handler);
+
+    let request_transfer = warp::path!("houses" / String / "transfer")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(request_transfer_handler);
+
+    let accept_transfer = warp::path!("houses" / String / "transfer" / "accept")
+        .and(warp::post())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(accept_transfer_handler);
+
+    let cancel_transfer = warp::path!("houses" / String / "transfer")
+        .and(warp::delete())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(cancel_transfer_handler);
+
+    add_member
+        .or(set_member_role)
+        .or(remove_member)
+        .or(leave_house)
+        .or(request_transfer)
+        .or(accept_transfer)
+        .or(cancel_transfer)
+}
+
+async fn add_member_handler(
+    house_id: String,
+    form: AddHouseMemberForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    // 新成员需要本人同意，这里发出邀请，对方接受后才会加入
+    let invitation = create_invitation(
+        &storage,
+        &house_id,
+        &user,
+        CreateHouseInvitationForm {
+            username: Some(form.username),
+            role: form.role,
+            expires_in_hours: None,
+        },
+    )?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&invitation),
+        warp::http::StatusCode::ACCEPTED,
+    ))
+}
+
+async fn set_member_role_handler(
+    house_id: String,
+    member_id: String,
+    form: SetMemberRoleForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    if form.role == HouseRole::Owner {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = find_house(houses, &house_id)?;
+            let my_role = check_role(house, &user, HouseRole::Manager)?;
+            if house.archived {
+                return Err(AppError::HouseArchived);
+            }
+            let member = house
+                .members
+                .iter_mut()
+                .find(|m| m.user_id == member_id)
+                .ok_or(AppError::UserNotFound)?;
+
+            // 只有创建人可以任免 manager
+            if my_role != HouseRole::Owner
+                && (member.role == HouseRole::Manager || form.role == HouseRole::Manager)
+            {
+                return Err(AppError::PermissionDenied);
+            }
+            member.role = form.role;
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Member role updated successfully"
+    })))
+}
+
+async fn remove_member_handler(
+    house_id: String,
+    member_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = find_house(houses, &house_id)?;
+            let my_role = check_role(house, &user, HouseRole::Manager)?;
+            if house.archived {
+                return Err(AppError::HouseArchived);
+            }
+            let index = house
+                .members
+                .iter()
+                .position(|m| m.user_id == member_id)
+                .ok_or(AppError::UserNotFound)?;
+
+            // manager 只能移除 viewer 和 editor，自己退出请使用 leave
+            if my_role != HouseRole::Owner && house.members[index].role == HouseRole::Manager {
+                return Err(AppError::PermissionDenied);
+            }
+            house.members.remove(index);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Member removed successfully"
+    })))
+}
+
+async fn leave_house_handler(
+    house_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = find_house(houses, &house_id)?;
+            match house.role_of(&user.id) {
+                // 创建人不能退出，需要先转让或删除房屋
+                Some(HouseRole::Owner) => Err(AppError::OwnerCannotLeave),
+                Some(_) => {
+                    house.members.retain(|m| m.user_id != user.id);
+                    Ok(())
+                }
+                None => Err(AppError::PermissionDenied),
+            }
+        })
+        .map_err(warp::reject::custom)?;
+    log::info!("User {} left house {}", user.username, house_id);
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Left house successfully"
+    })))
+}
+
+async fn request_transfer_handler(
+    house_id: String,
+    form: TransferOwnershipForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = find_house(houses, &house_id)?;
+            check_role(house, &user, HouseRole::Owner)?;
+            // 只能转让给现有成员，重复发起时替换之前的提名
+            if !house.members.iter().any(|m| m.user_id == form.user_id) {
+                return Err(AppError::UserNotFound);
+            }
+            house.pending_transfer = Some(OwnershipTransfer {
+                to: form.user_id.clone(),
+                requested_at: chrono::Utc::now(),
+            });
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "message": "Ownership transfer requested, waiting for the new owner to accept"
+        })),
+        warp::http::StatusCode::ACCEPTED,
+    ))
+}
+
+async fn accept_transfer_handler(
+    house_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = find_house(houses, &house_id)?;
+            // 被提名人在接受前退出或被移除时，提名失效
+            let nominated = house
+                .pending_transfer
+                .as_ref()
+                .is_some_and(|t| t.to == user.id);
+            if !nominated || !house.members.iter().any(|m| m.user_id == user.id) {
+                return Err(AppError::NotFound);
+            }
+            let former_username = users
+                .iter()
+                .find(|u| u.id == house.creator)
+                .map(|u| u.username.clone());
+            house.transfer_to(&user.id, former_username);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+    log::info!(
+        "User {} accepted ownership of house {}",
+        user.username,
+        house_id
+    );
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "House ownership transferred successfully"
+    })))
+}
+
+async fn cancel_transfer_handler(
+    house_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            let house = find_house(houses, &house_id)?;
+            let transfer = house.pending_transfer.as_ref().ok_or(AppError::NotFound)?;
+            // 创建人可以撤回，被提名人可以拒绝
+            if house.creator != user.id && transfer.to != user.id {
+                return Err(AppError::PermissionDenied);
+            }
+            house.pending_transfer = None;
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Ownership transfer cancelled"
+    })))
+}
+
+fn find_house<'a>(houses: &'a mut [House], house_id: &str) -> Result<&'a mut House, AppError> {
+    houses
+        .iter_mut()
+        .find(|h| h.id == house_id)
+        .ok_or(AppError::HouseNotFound)
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
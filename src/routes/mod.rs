@@ -11,21 +11,72 @@ pub fn combine_routes(
     let api_prefix = warp::path("api");
 
     let auth = auth_routes(file_storage.clone());
+    let account = account_routes(file_storage.clone());
+    let admin = admin_routes(file_storage.clone());
+    let areas = area_routes(file_storage.clone());
     let houses = houses_routes(file_storage.clone());
+    let house_invitations = house_invitation_routes(file_storage.clone());
+    let members = member_routes(file_storage.clone());
     let images = image_routes(file_storage.clone());
+    let invites = invite_routes(file_storage.clone());
+    let sessions = session_routes(file_storage.clone());
+    let templates = template_routes(file_storage.clone());
+    let tokens = token_routes(file_storage.clone());
+    let trash = trash_routes(file_storage.clone());
+    let users = user_routes(file_storage.clone());
     let backup = backup::backup_routes();
 
-    let api_routes = api_prefix.and(auth.or(houses).or(images).or(backup));
+    let api_routes = api_prefix.and(csrf_filter()).and(
+        auth.or(account)
+            .or(admin)
+            .or(areas)
+            .or(houses)
+            .or(house_invitations)
+            .or(members)
+            .or(images)
+            .or(invites)
+            .or(sessions)
+            .or(templates)
+            .or(tokens)
+            .or(trash)
+            .or(users)
+            .or(backup),
+    );
 
     api_routes.or(static_files_routes(static_path))
 }
 
+pub mod account;
+pub mod admin;
+pub mod areas;
 pub mod auth;
 pub mod backup;
+pub mod csrf;
+pub mod house_invitations;
 pub mod houses;
+pub mod invites;
+pub mod members;
+pub mod proxy_auth;
+pub mod sessions;
 pub mod static_files;
+pub mod templates;
+pub mod tokens;
+pub mod trash;
+pub mod user;
 
+use self::account::account_routes;
+use self::admin::admin_routes;
+use self::areas::area_routes;
 use self::auth::auth_routes;
+use self::csrf::csrf_filter;
+use self::house_invitations::house_invitation_routes;
 use self::houses::houses_routes;
 use self::images::image_routes;
+use self::invites::invite_routes;
+use self::members::member_routes;
+use self::sessions::session_routes;
 use self::static_files::static_files_routes;
+use self::templates::template_routes;
+use self::tokens::token_routes;
+use self::trash::trash_routes;
+use self::user::user_routes;
@@ -0,0 +1,142 @@
+use crate::{
+    models::{error::AppError, user::User},
+    storage::file_storage::FileStorage,
+};
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::OnceLock;
+use warp::http::HeaderMap;
+
+// 反向代理认证配置，只在设置了 HOUSE_KEEPER_PROXY_AUTH_HEADER 时启用
+pub struct ProxyAuthConfig {
+    pub header: String,
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+impl ProxyAuthConfig {
+    pub fn get() -> Option<&'static ProxyAuthConfig> {
+        static CONFIG: OnceLock<Option<ProxyAuthConfig>> = OnceLock::new();
+        CONFIG.get_or_init(Self::from_env).as_ref()
+    }
+
+    fn from_env() -> Option<Self> {
+        let header = std::env::var("HOUSE_KEEPER_PROXY_AUTH_HEADER")
+            .ok()
+            .map(|h| h.trim().to_string())
+            .filter(|h| !h.is_empty())?;
+        let trusted_proxies = std::env::var("HOUSE_KEEPER_TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| match parse_net(s) {
+                Some(net) => Some(net),
+                None => {
+                    log::warn!("Ignoring invalid trusted proxy range: {}", s);
+                    None
+                }
+            })
+            .collect();
+        Some(Self {
+            header,
+            trusted_proxies,
+        })
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+}
+
+// 支持单个 IP 或 CIDR 网段
+fn parse_net(s: &str) -> Option<IpNet> {
+    s.parse::<IpNet>()
+        .ok()
+        .or_else(|| s.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+// 来自受信任代理的请求从右向左遍历 X-Forwarded-For，跳过受信任的代理，
+// 取第一个不受信任的地址作为客户端 IP；最左侧的地址可由客户端伪造，不能直接使用
+pub fn client_ip(remote: Option<SocketAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+    let remote_ip = remote?.ip();
+    let config = match ProxyAuthConfig::get() {
+        Some(config) if config.is_trusted(remote_ip) => config,
+        _ => return Some(remote_ip),
+    };
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .collect::<Vec<_>>();
+
+    let mut client = remote_ip;
+    for entry in forwarded.into_iter().rev() {
+        // 无法解析的地址视为不可信，停在最后一个可信的代理上
+        let Ok(ip) = entry.trim().parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !config.is_trusted(ip) {
+            break;
+        }
+    }
+    Some(client)
+}
+
+// 根据受信任代理传递的用户名请求头查找用户，首次出现的用户名会自动创建账号
+pub fn resolve_proxy_user(
+    storage: &FileStorage,
+    remote: Option<SocketAddr>,
+    headers: &HeaderMap,
+) -> Result<Option<User>, AppError> {
+    let config = match ProxyAuthConfig::get() {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+    let username = match headers
+        .get(config.header.as_str())
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+    {
+        Some(username) => username,
+        None => return Ok(None),
+    };
+
+    // 只信任来自配置网段的请求头，防止客户端直接伪造
+    if !remote.is_some_and(|addr| config.is_trusted(addr.ip())) {
+        log::warn!(
+            "Ignoring {} header from untrusted address {:?}",
+            config.header,
+            remote
+        );
+        return Ok(None);
+    }
+
+    if let Some(user) = storage.with_auth_index(|index| index.user_by_username(username))? {
+        if user.disabled {
+            return Err(AppError::AccountDisabled);
+        }
+        return Ok(Some(user));
+    }
+
+    // 自动创建的账号使用随机密码，只能通过代理登录，需要时可由管理员重置密码
+    let password = storage.hash_password(&uuid::Uuid::new_v4().to_string())?;
+
+    // 在锁内再次查找用户名，避免同一用户的并发首次请求创建重复账号
+    let user = storage.update_json("user.json", |users: &mut Vec<User>| {
+        if let Some(user) = users.iter().find(|u| u.username == username) {
+            if user.disabled {
+                return Err(AppError::AccountDisabled);
+            }
+            return Ok(user.clone());
+        }
+        let user = User::new(username, &password);
+        users.push(user.clone());
+        log::info!("Provisioned user {} from proxy authentication", username);
+        Ok(user)
+    })?;
+
+    Ok(Some(user))
+}
@@ -0,0 +1,114 @@
+use crate::{
+    models::{error::AppError, session::Session, user::User},
+    routes::auth::auth_filter,
+    storage::file_storage::FileStorage,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+// 返回给前端的会话信息，不包含 token
+#[derive(Debug, Serialize)]
+struct SessionView {
+    id: String,
+    user_agent: Option<String>,
+    created_at: DateTime<Utc>,
+    last_seen_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    current: bool,
+}
+
+pub fn session_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let list_sessions = warp::path!("sessions")
+        .and(warp::get())
+        .and(warp::cookie::optional("token"))
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(list_sessions_handler);
+
+    let revoke_session = warp::path!("sessions" / String)
+        .and(warp::delete())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(revoke_session_handler);
+
+    let revoke_all_sessions = warp::path!("sessions")
+        .and(warp::delete())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(revoke_all_sessions_handler);
+
+    list_sessions.or(revoke_session).or(revoke_all_sessions)
+}
+
+async fn list_sessions_handler(
+    token: Option<String>,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let sessions: Vec<Session> = storage
+        .read_json("session.json")
+        .map_err(warp::reject::custom)?;
+
+    let my_sessions = sessions
+        .into_iter()
+        .filter(|s| s.user_id == user.id && !s.is_expired())
+        .map(|s| SessionView {
+            current: token.as_deref() == Some(s.token.as_str()),
+            id: s.id,
+            user_agent: s.user_agent,
+            created_at: s.created_at,
+            last_seen_at: s.last_seen_at,
+            expires_at: s.expires_at,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(warp::reply::json(&my_sessions))
+}
+
+async fn revoke_session_handler(
+    session_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("session.json", |sessions: &mut Vec<Session>| {
+            // 只能撤销自己的会话
+            let index = sessions
+                .iter()
+                .position(|s| s.id == session_id && s.user_id == user.id)
+                .ok_or(AppError::NotFound)?;
+            sessions.remove(index);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Session revoked successfully"
+    })))
+}
+
+async fn revoke_all_sessions_handler(
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("session.json", |sessions: &mut Vec<Session>| {
+            sessions.retain(|s| s.user_id != user.id);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "All sessions revoked successfully"
+    })))
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
@@ -0,0 +1,119 @@
+use crate::{
+    models::{
+        error::AppError,
+        house::{House, HouseDetail},
+        template::{HouseTemplate, SaveTemplateForm},
+        user::User,
+    },
+    routes::{auth::auth_filter, houses::require_role},
+    storage::file_storage::FileStorage,
+};
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+pub fn template_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let save_template = warp::path!("houses" / String / "template")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(save_template_handler);
+
+    let get_my_templates = warp::path!("templates")
+        .and(warp::get())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(get_my_templates_handler);
+
+    let delete_template = warp::path!("templates" / String)
+        .and(warp::delete())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(delete_template_handler);
+
+    save_template.or(get_my_templates).or(delete_template)
+}
+
+async fn save_template_handler(
+    house_id: String,
+    form: SaveTemplateForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let houses: Vec<House> = storage
+        .read_json("house.json")
+        .map_err(warp::reject::custom)?;
+    let house = houses
+        .iter()
+        .find(|h| h.id == house_id)
+        .ok_or_else(|| warp::reject::custom(AppError::HouseNotFound))?;
+    // 能查看的房屋都可以保存为自己的模板；连同图片一起保存需要 editor 及以上角色
+    require_role(house, &user, form.mode.min_role())?;
+
+    let house_detail: HouseDetail = storage
+        .read_json(&format!("house/{}.json", house_id))
+        .map_err(warp::reject::custom)?;
+    let name = form
+        .name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| house.name.clone());
+    let template = HouseTemplate::new(&name, &user.id, form.mode, &house_detail.items);
+
+    storage
+        .update_json("template.json", |templates: &mut Vec<HouseTemplate>| {
+            templates.push(template.clone());
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&template),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+async fn get_my_templates_handler(
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let templates: Vec<HouseTemplate> = storage
+        .read_json("template.json")
+        .map_err(warp::reject::custom)?;
+
+    let my_templates = templates
+        .into_iter()
+        .filter(|t| t.owner == user.id)
+        .collect::<Vec<_>>();
+
+    Ok(warp::reply::json(&my_templates))
+}
+
+async fn delete_template_handler(
+    template_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("template.json", |templates: &mut Vec<HouseTemplate>| {
+            let index = templates
+                .iter()
+                .position(|t| t.id == template_id && t.owner == user.id)
+                .ok_or(AppError::NotFound)?;
+            templates.remove(index);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Template deleted successfully"
+    })))
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
@@ -0,0 +1,134 @@
+use crate::{
+    models::{
+        api_token::{ApiToken, CreateApiTokenForm, TokenScope},
+        error::AppError,
+        user::User,
+    },
+    routes::auth::auth_filter,
+    storage::file_storage::FileStorage,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+// 返回给前端的令牌信息，不包含 token 哈希
+#[derive(Debug, Serialize)]
+struct ApiTokenView {
+    id: String,
+    name: String,
+    scope: TokenScope,
+    house_ids: Option<Vec<String>>,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiToken> for ApiTokenView {
+    fn from(t: ApiToken) -> Self {
+        Self {
+            id: t.id,
+            name: t.name,
+            scope: t.scope,
+            house_ids: t.house_ids,
+            created_at: t.created_at,
+            last_used_at: t.last_used_at,
+        }
+    }
+}
+
+pub fn token_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let create_token = warp::path!("tokens")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(create_token_handler);
+
+    let list_tokens = warp::path!("tokens")
+        .and(warp::get())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(list_tokens_handler);
+
+    let revoke_token = warp::path!("tokens" / String)
+        .and(warp::delete())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(revoke_token_handler);
+
+    create_token.or(list_tokens).or(revoke_token)
+}
+
+async fn create_token_handler(
+    form: CreateApiTokenForm,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    if form.name.trim().is_empty() {
+        return Err(warp::reject::custom(AppError::ParameterError));
+    }
+
+    let (api_token, secret) = ApiToken::new(&user.id, &form);
+    storage
+        .update_json("api_token.json", |tokens: &mut Vec<ApiToken>| {
+            tokens.push(api_token.clone());
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    // 明文 token 只在创建时返回一次
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "token": secret,
+            "info": ApiTokenView::from(api_token),
+        })),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+async fn list_tokens_handler(
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let tokens: Vec<ApiToken> = storage
+        .read_json("api_token.json")
+        .map_err(warp::reject::custom)?;
+
+    let my_tokens = tokens
+        .into_iter()
+        .filter(|t| t.user_id == user.id)
+        .map(ApiTokenView::from)
+        .collect::<Vec<_>>();
+
+    Ok(warp::reply::json(&my_tokens))
+}
+
+async fn revoke_token_handler(
+    token_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    storage
+        .update_json("api_token.json", |tokens: &mut Vec<ApiToken>| {
+            // 只能撤销自己的令牌
+            let index = tokens
+                .iter()
+                .position(|t| t.id == token_id && t.user_id == user.id)
+                .ok_or(AppError::NotFound)?;
+            tokens.remove(index);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Token revoked successfully"
+    })))
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
@@ -0,0 +1,131 @@
+use crate::{
+    models::{error::AppError, house::House, trash::TrashedHouse, user::User},
+    routes::auth::auth_filter,
+    storage::{file_storage::FileStorage, trash::purge_houses},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+// 回收站列表附带彻底清除的时间
+#[derive(Debug, Serialize)]
+struct TrashedHouseView {
+    #[serde(flatten)]
+    trashed: TrashedHouse,
+    purge_at: DateTime<Utc>,
+}
+
+pub fn trash_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let list_trash = warp::path!("trash")
+        .and(warp::get())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(list_trash_handler);
+
+    let restore_house = warp::path!("trash" / String / "restore")
+        .and(warp::post())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(restore_house_handler);
+
+    let purge_house = warp::path!("trash" / String)
+        .and(warp::delete())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(purge_house_handler);
+
+    list_trash.or(restore_house).or(purge_house)
+}
+
+async fn list_trash_handler(
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let trash: Vec<TrashedHouse> = storage
+        .read_json("trash.json")
+        .map_err(warp::reject::custom)?;
+
+    // 只返回当前用户创建的房屋
+    let views = trash
+        .into_iter()
+        .filter(|t| t.house.creator == user.id)
+        .map(|trashed| TrashedHouseView {
+            purge_at: trashed.purge_at(),
+            trashed,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(warp::reply::json(&views))
+}
+
+async fn restore_house_handler(
+    house_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let mut house = storage
+        .update_json("trash.json", |trash: &mut Vec<TrashedHouse>| {
+            let index = trash
+                .iter()
+                .position(|t| t.house.id == house_id)
+                .ok_or(AppError::HouseNotFound)?;
+            if trash[index].house.creator != user.id {
+                return Err(AppError::PermissionDenied);
+            }
+            Ok(trash.remove(index).house)
+        })
+        .map_err(warp::reject::custom)?;
+
+    // 删除期间注销了账号的成员不再恢复
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+    house
+        .members
+        .retain(|m| users.iter().any(|u| u.id == m.user_id));
+
+    storage
+        .update_json("house.json", |houses: &mut Vec<House>| {
+            houses.push(house);
+            Ok(())
+        })
+        .map_err(warp::reject::custom)?;
+    log::info!("User {} restored house {}", user.username, house_id);
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "id": house_id,
+        "message": "House restored successfully"
+    })))
+}
+
+async fn purge_house_handler(
+    house_id: String,
+    user: User,
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let trash: Vec<TrashedHouse> = storage
+        .read_json("trash.json")
+        .map_err(warp::reject::custom)?;
+    let trashed = trash
+        .iter()
+        .find(|t| t.house.id == house_id)
+        .ok_or_else(|| warp::reject::custom(AppError::HouseNotFound))?;
+    if trashed.house.creator != user.id {
+        return Err(warp::reject::custom(AppError::PermissionDenied));
+    }
+
+    purge_houses(&storage, |t| t.house.id == house_id).map_err(warp::reject::custom)?;
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "House permanently deleted"
+    })))
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
@@ -0,0 +1,84 @@
+use crate::{models::user::User, routes::auth::auth_filter, storage::file_storage::FileStorage};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use warp::{Filter, Rejection};
+
+// 单次搜索最多返回的用户数
+const MAX_DIRECTORY_RESULTS: usize = 20;
+
+#[derive(Debug, Deserialize)]
+struct UserQuery {
+    #[serde(default)]
+    query: String,
+}
+
+// 用户目录只暴露成员选择所需的字段
+#[derive(Debug, Serialize)]
+struct UserSummary {
+    id: String,
+    username: String,
+    display_name: Option<String>,
+}
+
+pub fn user_routes(
+    storage: FileStorage,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("users")
+        .and(warp::get())
+        .and(warp::query::<UserQuery>())
+        .and(auth_filter(storage.clone()))
+        .and(with_storage(storage.clone()))
+        .and_then(search_users_handler)
+}
+
+async fn search_users_handler(
+    query: UserQuery,
+    _user: User, // 校验登录用户
+    storage: FileStorage,
+) -> Result<impl warp::Reply, Rejection> {
+    let keyword = query.query.trim().to_lowercase();
+    // 不允许空关键字列出全部用户
+    if keyword.is_empty() {
+        return Ok(warp::reply::json(&Vec::<UserSummary>::new()));
+    }
+
+    let users: Vec<User> = storage
+        .read_json("user.json")
+        .map_err(warp::reject::custom)?;
+
+    // 用户名或显示名称包含关键字即匹配，用户名前缀匹配的排在前面
+    let mut matched = users
+        .into_iter()
+        .filter(|u| !u.disabled)
+        .filter(|u| {
+            u.username.to_lowercase().contains(&keyword)
+                || u.display_name
+                    .as_ref()
+                    .is_some_and(|d| d.to_lowercase().contains(&keyword))
+        })
+        .collect::<Vec<_>>();
+    matched.sort_by_key(|u| {
+        (
+            !u.username.to_lowercase().starts_with(&keyword),
+            u.username.clone(),
+        )
+    });
+
+    let results = matched
+        .into_iter()
+        .take(MAX_DIRECTORY_RESULTS)
+        .map(|u| UserSummary {
+            id: u.id,
+            username: u.username,
+            display_name: u.display_name,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(warp::reply::json(&results))
+}
+
+fn with_storage(
+    storage: FileStorage,
+) -> impl Filter<Extract = (FileStorage,), Error = Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
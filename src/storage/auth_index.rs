@@ -0,0 +1,63 @@
+use crate::{
+    models::{api_token::ApiToken, error::AppError, session::Session, user::User},
+    storage::file_storage::FileStorage,
+};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+// 索引依赖的数据文件，任意一个被写入或修改时间变化都会使索引失效
+pub const INDEXED_FILES: [&str; 3] = ["user.json", "session.json", "api_token.json"];
+
+// 会话 token / 访问令牌哈希到用户的内存索引，避免每个请求都读取和解析 JSON 文件
+pub struct AuthIndex {
+    users: HashMap<String, User>,
+    sessions: HashMap<String, Session>,
+    api_tokens: HashMap<String, ApiToken>,
+    mtimes: [Option<SystemTime>; 3],
+}
+
+impl AuthIndex {
+    pub fn load(storage: &FileStorage, mtimes: [Option<SystemTime>; 3]) -> Result<Self, AppError> {
+        let users: Vec<User> = storage.read_json("user.json")?;
+        let sessions: Vec<Session> = storage.read_json("session.json")?;
+        let api_tokens: Vec<ApiToken> = storage.read_json("api_token.json")?;
+
+        Ok(Self {
+            users: users.into_iter().map(|u| (u.id.clone(), u)).collect(),
+            sessions: sessions.into_iter().map(|s| (s.token.clone(), s)).collect(),
+            api_tokens: api_tokens
+                .into_iter()
+                .map(|t| (t.token_hash.clone(), t))
+                .collect(),
+            mtimes,
+        })
+    }
+
+    pub fn is_fresh(&self, mtimes: &[Option<SystemTime>; 3]) -> bool {
+        self.mtimes == *mtimes
+    }
+
+    // 被禁用的账号视为不存在
+    fn active_user(&self, user_id: &str) -> Option<User> {
+        self.users.get(user_id).filter(|u| !u.disabled).cloned()
+    }
+
+    pub fn user_by_username(&self, username: &str) -> Option<User> {
+        self.users
+            .values()
+            .find(|u| u.username == username)
+            .cloned()
+    }
+
+    pub fn session_user(&self, token: &str) -> Option<(Session, User)> {
+        let session = self.sessions.get(token).filter(|s| !s.is_expired())?;
+        let user = self.active_user(&session.user_id)?;
+        Some((session.clone(), user))
+    }
+
+    pub fn api_token_user(&self, token_hash: &str) -> Option<(ApiToken, User)> {
+        let api_token = self.api_tokens.get(token_hash)?;
+        let user = self.active_user(&api_token.user_id)?;
+        Some((api_token.clone(), user))
+    }
+}
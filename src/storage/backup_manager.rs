@@ -38,7 +38,7 @@ impl BackupManager {
            .map(|p| p.metadata().and_then(|m| m.modified()))
            .transpose()?;
 
-        Ok(backup_modified.map_or(true, |bt| source_modified > bt))
+        Ok(backup_modified.is_none_or(|bt| source_modified > bt))
     }
 
     fn latest_backup_file(&self, file_path: &str) -> Result<Option<PathBuf>, AppError> {
@@ -0,0 +1,47 @@
+use crate::models::{
+    error::AppError,
+    house::{House, HouseDetail},
+};
+use crate::storage::file_storage::FileStorage;
+
+// 启动时检查 house.json 与 house/{id}.json 中的房屋名称是否一致，以 house.json 为准修复
+// 返回修复的房屋数量
+pub fn sync_house_names(storage: &FileStorage) -> Result<usize, AppError> {
+    let houses: Vec<House> = storage.read_json("house.json")?;
+    let mut repaired = 0;
+
+    for house in &houses {
+        let path = format!("house/{}.json", house.id);
+        let mut detail = match storage.read_json::<HouseDetail>(&path) {
+            Ok(detail) => detail,
+            Err(AppError::FileSystemError(_)) => {
+                // 详细数据文件丢失时重新创建一个空的
+                log::warn!("House detail {} is missing, recreating it", path);
+                let mut detail = HouseDetail::new();
+                detail.name = house.name.clone();
+                storage.write_json(&path, &detail)?;
+                repaired += 1;
+                continue;
+            }
+            Err(e) => {
+                // 无法解析的文件保留原样，交给人工处理
+                log::error!("Skipping house {}: {}", house.id, e);
+                continue;
+            }
+        };
+
+        if detail.name != house.name {
+            log::warn!(
+                "Repairing name of house {}: {:?} -> {:?}",
+                house.id,
+                detail.name,
+                house.name
+            );
+            detail.name = house.name.clone();
+            storage.write_json(&path, &detail)?;
+            repaired += 1;
+        }
+    }
+
+    Ok(repaired)
+}
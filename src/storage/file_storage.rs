@@ -1,14 +1,21 @@
-use crate::models::{error::AppError, house::HouseDetail};
+use crate::models::error::AppError;
+use crate::storage::auth_index::{AuthIndex, INDEXED_FILES};
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, SaltString};
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
 use serde::{de::DeserializeOwned, Serialize};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
 use tempfile::NamedTempFile;
-use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct FileStorage {
     base_path: PathBuf,
+    auth_index: Arc<RwLock<Option<AuthIndex>>>,
+    // 串行化 update_json 的读取-修改-写回，避免并发修改互相覆盖
+    update_lock: Arc<Mutex<()>>,
 }
 
 impl FileStorage {
@@ -19,6 +26,8 @@ impl FileStorage {
         }
         Self {
             base_path: path.to_path_buf(),
+            auth_index: Arc::new(RwLock::new(None)),
+            update_lock: Arc::new(Mutex::new(())),
         }
     }
 
@@ -68,30 +77,90 @@ impl FileStorage {
             AppError::FileSystemError(format!("Failed to save file {}: {}", path, e))
         })?;
 
+        // 写入用户、会话或令牌数据时使认证索引失效
+        if INDEXED_FILES.contains(&path) {
+            *self.auth_index.write().unwrap() = None;
+        }
+
         Ok(())
     }
 
-    pub fn verify_password(&self, password: &str, stored_password: &str) -> Result<bool, AppError> {
-        Ok(password == stored_password)
+    // 在锁内读取 JSON 文件、修改后写回；f 返回错误时不写入
+    pub fn update_json<T, R>(
+        &self,
+        path: &str,
+        f: impl FnOnce(&mut T) -> Result<R, AppError>,
+    ) -> Result<R, AppError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let _guard = self.update_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut data: T = self.read_json(path)?;
+        let result = f(&mut data)?;
+        self.write_json(path, &data)?;
+        Ok(result)
     }
 
-    // 房屋详细数据版本控制写入
-    pub fn write_house_detail(
-        &self,
-        house_id: &str,
-        new_data: &HouseDetail,
-        expected_version: &str,
-    ) -> Result<(), AppError> {
-        let current: HouseDetail = self.read_json(&format!("house/{}.json", house_id))?;
-        if current.version != expected_version {
-            return Err(AppError::InvalidVersion);
+    // 在认证索引上执行查询，索引不存在或数据文件在磁盘上被修改过时重新加载
+    pub fn with_auth_index<R>(&self, f: impl FnOnce(&AuthIndex) -> R) -> Result<R, AppError> {
+        let mtimes = INDEXED_FILES.map(|path| self.modified_time(path));
+        {
+            let index = self.auth_index.read().unwrap();
+            if let Some(index) = index.as_ref().filter(|i| i.is_fresh(&mtimes)) {
+                return Ok(f(index));
+            }
         }
 
-        let new_version = Uuid::new_v4().to_string();
-        let mut updated_data = new_data.clone();
-        updated_data.version = new_version;
+        let index = AuthIndex::load(self, mtimes)?;
+        let result = f(&index);
+        *self.auth_index.write().unwrap() = Some(index);
+        Ok(result)
+    }
+
+    fn modified_time(&self, path: &str) -> Option<SystemTime> {
+        fs::metadata(self.base_path.join(path))
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    pub fn verify_password(&self, password: &str, stored_password: &str) -> Result<bool, AppError> {
+        // 旧数据中的明文密码，使用常量时间比较，登录成功后会被重新哈希
+        if self.needs_rehash(stored_password) {
+            return Ok(constant_time_eq(
+                password.as_bytes(),
+                stored_password.as_bytes(),
+            ));
+        }
 
-        self.write_json(&format!("house/{}.json", house_id), &updated_data)
+        let parsed = PasswordHash::new(stored_password)
+            .map_err(|e| AppError::ParseError(format!("Invalid password hash: {}", e)))?;
+        Ok(Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok())
+    }
+
+    // 使用 Argon2id 生成 PHC 格式的密码哈希
+    pub fn hash_password(&self, password: &str) -> Result<String, AppError> {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| {
+                log::error!("Failed to hash password: {}", e);
+                AppError::InternalServerError
+            })
+    }
+
+    // 非 Argon2 PHC 字符串即视为明文密码
+    pub fn needs_rehash(&self, stored_password: &str) -> bool {
+        !stored_password.starts_with("$argon2")
+    }
+
+    pub fn file_size(&self, path: &str) -> Result<u64, AppError> {
+        let full_path = self.base_path.join(path);
+        fs::metadata(full_path).map(|m| m.len()).map_err(|e| {
+            AppError::FileSystemError(format!("Failed to read metadata of {}: {}", path, e))
+        })
     }
 
     pub fn delete_file(&self, path: &str) -> Result<(), std::io::Error> {
@@ -143,3 +212,11 @@ impl FileStorage {
         Ok(bytes)
     }
 }
+
+// 常量时间比较，避免通过响应耗时推测密码内容
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,72 @@
+use crate::models::{error::AppError, house::HouseDetail};
+use crate::storage::file_storage::FileStorage;
+
+// 每个房屋保留的历史快照数量
+const MAX_HISTORY_SNAPSHOTS: usize = 20;
+
+fn history_path(house_id: &str) -> String {
+    format!("house_history/{}.json", house_id)
+}
+
+// 修改房屋详细数据，version 变化时把修改前的数据保存为历史快照，
+// 旧版本的客户端保存时以快照为基础做三方合并
+pub fn update_house_detail<R>(
+    storage: &FileStorage,
+    house_id: &str,
+    f: impl FnOnce(&mut HouseDetail) -> Result<R, AppError>,
+) -> Result<R, AppError> {
+    storage.update_json(
+        &format!("house/{}.json", house_id),
+        |detail: &mut HouseDetail| {
+            let previous = detail.clone();
+            let result = f(detail)?;
+            if detail.version != previous.version {
+                record_snapshot(storage, house_id, previous)?;
+            }
+            Ok(result)
+        },
+    )
+}
+
+// 按版本号查找历史快照
+pub fn find_snapshot(
+    storage: &FileStorage,
+    house_id: &str,
+    version: &str,
+) -> Result<Option<HouseDetail>, AppError> {
+    Ok(read_history(storage, house_id)?
+        .into_iter()
+        .find(|s| s.version == version))
+}
+
+// 彻底删除房屋时一并删除历史快照
+pub fn delete_history(storage: &FileStorage, house_id: &str) {
+    let path = history_path(house_id);
+    if let Err(e) = storage.delete_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to delete {}: {}", path, e);
+        }
+    }
+}
+
+fn record_snapshot(
+    storage: &FileStorage,
+    house_id: &str,
+    snapshot: HouseDetail,
+) -> Result<(), AppError> {
+    let mut history = read_history(storage, house_id)?;
+    history.push(snapshot);
+    if history.len() > MAX_HISTORY_SNAPSHOTS {
+        history.drain(..history.len() - MAX_HISTORY_SNAPSHOTS);
+    }
+    storage.write_json(&history_path(house_id), &history)
+}
+
+fn read_history(storage: &FileStorage, house_id: &str) -> Result<Vec<HouseDetail>, AppError> {
+    // 还没有历史快照时文件不存在
+    match storage.read_json(&history_path(house_id)) {
+        Ok(history) => Ok(history),
+        Err(AppError::FileSystemError(_)) => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// 二次验证的有效时长（秒）
+const CHALLENGE_TTL_SECS: u64 = 5 * 60;
+// 每个二次验证最多允许尝试的次数
+const MAX_ATTEMPTS: u32 = 5;
+
+pub struct LoginChallenge {
+    pub user_id: String,
+    pub user_agent: Option<String>,
+    expires_at: Instant,
+    attempts: u32,
+}
+
+// 密码校验通过、等待二次验证的登录请求，只保存在内存中
+#[derive(Clone, Default)]
+pub struct LoginChallenges {
+    challenges: Arc<Mutex<HashMap<String, LoginChallenge>>>,
+}
+
+impl LoginChallenges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create(&self, user_id: &str, user_agent: Option<String>) -> String {
+        let mut challenges = self.challenges.lock().unwrap();
+        let now = Instant::now();
+        challenges.retain(|_, c| c.expires_at > now);
+
+        let id = Uuid::new_v4().simple().to_string();
+        challenges.insert(
+            id.clone(),
+            LoginChallenge {
+                user_id: user_id.to_string(),
+                user_agent,
+                expires_at: now + Duration::from_secs(CHALLENGE_TTL_SECS),
+                attempts: 0,
+            },
+        );
+        id
+    }
+
+    // 记录一次尝试，返回对应的用户和 User-Agent；过期或尝试次数用完时返回 None
+    pub fn attempt(&self, id: &str) -> Option<(String, Option<String>)> {
+        let mut challenges = self.challenges.lock().unwrap();
+        let challenge = challenges.get_mut(id)?;
+        if challenge.expires_at <= Instant::now() || challenge.attempts >= MAX_ATTEMPTS {
+            challenges.remove(id);
+            return None;
+        }
+        challenge.attempts += 1;
+        Some((challenge.user_id.clone(), challenge.user_agent.clone()))
+    }
+
+    pub fn complete(&self, id: &str) {
+        self.challenges.lock().unwrap().remove(id);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::models::error::AppError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// 连续失败多少次之后开始退避
+const FREE_ATTEMPTS: u32 = 3;
+// 退避等待的最长时间（秒）
+const MAX_BACKOFF_SECS: u64 = 60;
+// 连续失败多少次之后锁定
+const LOCKOUT_ATTEMPTS: u32 = 10;
+// 锁定时长（秒）
+const LOCKOUT_SECS: u64 = 15 * 60;
+// 超过该时长没有失败记录则清除（秒）
+const RECORD_TTL_SECS: u64 = 60 * 60;
+
+struct FailureRecord {
+    failures: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl FailureRecord {
+    // 下一次允许尝试登录的时间
+    fn next_attempt_at(&self) -> Instant {
+        if let Some(locked_until) = self.locked_until {
+            return locked_until;
+        }
+        if self.failures < FREE_ATTEMPTS {
+            return self.last_failure;
+        }
+        let backoff = 2u64
+            .saturating_pow(self.failures - FREE_ATTEMPTS)
+            .min(MAX_BACKOFF_SECS);
+        self.last_failure + Duration::from_secs(backoff)
+    }
+}
+
+// 登录失败计数器，按用户名和 IP 分别统计，只保存在内存中
+#[derive(Clone, Default)]
+pub struct LoginThrottle {
+    records: Arc<Mutex<HashMap<String, FailureRecord>>>,
+}
+
+impl LoginThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 检查这些 key 当前是否允许尝试登录
+    pub fn check(&self, keys: &[String]) -> Result<(), AppError> {
+        let records = self.records.lock().unwrap();
+        let now = Instant::now();
+        for key in keys {
+            if let Some(record) = records.get(key) {
+                if record.next_attempt_at() > now {
+                    return Err(AppError::TooManyAttempts);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record_failure(&self, keys: &[String]) {
+        let mut records = self.records.lock().unwrap();
+        let now = Instant::now();
+        records.retain(|_, r| {
+            now.duration_since(r.last_failure) < Duration::from_secs(RECORD_TTL_SECS)
+                && r.locked_until.is_none_or(|t| t > now)
+        });
+
+        for key in keys {
+            let record = records.entry(key.clone()).or_insert(FailureRecord {
+                failures: 0,
+                last_failure: now,
+                locked_until: None,
+            });
+            record.failures += 1;
+            record.last_failure = now;
+            if record.failures >= LOCKOUT_ATTEMPTS && record.locked_until.is_none() {
+                record.locked_until = Some(now + Duration::from_secs(LOCKOUT_SECS));
+                log::warn!(
+                    "Login locked for {} after {} failed attempts",
+                    key,
+                    record.failures
+                );
+            }
+        }
+    }
+
+    pub fn record_success(&self, key: &str) {
+        self.records.lock().unwrap().remove(key);
+    }
+}
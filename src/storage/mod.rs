@@ -1,2 +1,8 @@
+pub mod auth_index;
 pub mod backup_manager;
+pub mod consistency;
 pub mod file_storage;
+pub mod history;
+pub mod login_challenge;
+pub mod login_throttle;
+pub mod trash;
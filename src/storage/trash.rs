@@ -0,0 +1,137 @@
+use crate::models::{
+    error::AppError,
+    house::{House, HouseDetail},
+    image::ImageRecord,
+    template::HouseTemplate,
+    trash::TrashedHouse,
+    user::User,
+};
+use crate::storage::{file_storage::FileStorage, history::delete_history};
+use chrono::Utc;
+use std::collections::HashSet;
+
+// 彻底清除回收站中超过保留期的房屋，返回清除的数量
+pub fn purge_expired(storage: &FileStorage) -> Result<usize, AppError> {
+    let now = Utc::now();
+    purge_houses(storage, |t| t.purge_at() <= now)
+}
+
+// 彻底清除回收站中符合条件的房屋：删除详细数据文件，以及不再被引用的图片
+pub fn purge_houses(
+    storage: &FileStorage,
+    predicate: impl Fn(&TrashedHouse) -> bool,
+) -> Result<usize, AppError> {
+    let trash: Vec<TrashedHouse> = storage.read_json("trash.json")?;
+    let planned_ids = trash
+        .iter()
+        .filter(|t| predicate(t))
+        .map(|t| t.house.id.clone())
+        .collect::<HashSet<_>>();
+    if planned_ids.is_empty() {
+        return Ok(0);
+    }
+
+    // 修改任何数据之前先读取图片归属记录，并算出仍被其他房屋（包括回收站中的）、模板或头像引用的图片
+    let records: Vec<ImageRecord> = storage.read_json("image.json")?;
+    let in_use = referenced_images(storage, &planned_ids)?;
+
+    let purged = storage.update_json("trash.json", |trash: &mut Vec<TrashedHouse>| {
+        let (purged, kept): (Vec<_>, Vec<_>) = std::mem::take(trash)
+            .into_iter()
+            .partition(|t| planned_ids.contains(&t.house.id) && predicate(t));
+        *trash = kept;
+        Ok(purged)
+    })?;
+    if purged.is_empty() {
+        return Ok(0);
+    }
+
+    // 收集被清除房屋引用或上传到这些房屋的图片，然后删除详细数据文件
+    let purged_ids = purged
+        .iter()
+        .map(|t| t.house.id.clone())
+        .collect::<HashSet<_>>();
+    let mut candidates = HashSet::new();
+    for trashed in &purged {
+        let path = format!("house/{}.json", trashed.house.id);
+        if let Ok(detail) = storage.read_json::<HouseDetail>(&path) {
+            candidates.extend(detail.image_names());
+        }
+        if let Err(e) = storage.delete_file(&path) {
+            log::warn!("Failed to delete {}: {}", path, e);
+        }
+        delete_history(storage, &trashed.house.id);
+        log::info!(
+            "Purged house {} ({}) deleted at {}",
+            trashed.house.id,
+            trashed.house.name,
+            trashed.deleted_at
+        );
+    }
+    candidates.extend(
+        records
+            .iter()
+            .filter(|r| {
+                r.house_id
+                    .as_ref()
+                    .is_some_and(|id| purged_ids.contains(id))
+            })
+            .map(|r| r.file_name.clone()),
+    );
+
+    candidates.retain(|name| !in_use.contains(name) && !name.starts_with('.'));
+    for name in &candidates {
+        if let Err(e) = storage.delete_file(&format!("images/{}", name)) {
+            log::warn!("Failed to delete image {}: {}", name, e);
+        }
+    }
+
+    // 保留下来的图片去掉归属记录，下载时按引用它的房屋校验权限
+    storage.update_json("image.json", |records: &mut Vec<ImageRecord>| {
+        records.retain(|r| {
+            !candidates.contains(&r.file_name)
+                && !r
+                    .house_id
+                    .as_ref()
+                    .is_some_and(|id| purged_ids.contains(id))
+        });
+        Ok(())
+    })?;
+
+    Ok(purged.len())
+}
+
+// 当前房屋、回收站中的房屋（即将清除的 exclude 除外）、模板以及用户头像引用的所有图片
+fn referenced_images(
+    storage: &FileStorage,
+    exclude: &HashSet<String>,
+) -> Result<HashSet<String>, AppError> {
+    let houses: Vec<House> = storage.read_json("house.json")?;
+    let trash: Vec<TrashedHouse> = storage.read_json("trash.json")?;
+    let users: Vec<User> = storage.read_json("user.json")?;
+    let templates: Vec<HouseTemplate> = storage.read_json("template.json")?;
+
+    let mut names = users
+        .into_iter()
+        .filter_map(|u| u.avatar)
+        .collect::<HashSet<_>>();
+    for template in &templates {
+        template
+            .items
+            .iter()
+            .for_each(|a| names.extend(a.image_names()));
+    }
+    for id in houses.iter().map(|h| &h.id).chain(
+        trash
+            .iter()
+            .map(|t| &t.house.id)
+            .filter(|id| !exclude.contains(*id)),
+    ) {
+        // 个别详细数据文件无法读取时跳过，不影响其他房屋的清除
+        match storage.read_json::<HouseDetail>(&format!("house/{}.json", id)) {
+            Ok(detail) => names.extend(detail.image_names()),
+            Err(e) => log::warn!("Skipping images of house {}: {}", id, e),
+        }
+    }
+    Ok(names)
+}